@@ -0,0 +1,150 @@
+use crate::MAX_SQS_BATCH_SIZE;
+use aws_sdk_sqs::types::SendMessageBatchRequestEntry;
+
+/// SQS limits the total payload of a batch, and thus also of a single message, to 256 KiB.
+pub const MAX_SQS_BATCH_PAYLOAD_BYTES: usize = 256 * 1024;
+
+/// The size SQS accounts for an entry: its body plus name, data type and value of all attributes.
+pub fn entry_size(entry: &SendMessageBatchRequestEntry) -> usize {
+    let attributes_size = entry
+        .message_attributes()
+        .map(|attributes| {
+            attributes
+                .iter()
+                .map(|(name, value)| {
+                    name.len()
+                        + value.data_type().len()
+                        + value.string_value().map(str::len).unwrap_or_default()
+                        + value
+                            .binary_value()
+                            .map(|blob| blob.as_ref().len())
+                            .unwrap_or_default()
+                })
+                .sum::<usize>()
+        })
+        .unwrap_or_default();
+    entry.message_body().len() + attributes_size
+}
+
+/// Items larger than `max_bytes` on their own can never be sent and are returned separately.
+pub fn pack_by_size<T>(
+    items: Vec<T>,
+    size_of: impl Fn(&T) -> usize,
+    max_count: usize,
+    max_bytes: usize,
+) -> (Vec<Vec<T>>, Vec<T>) {
+    let mut batches = vec![];
+    let mut oversized = vec![];
+    let mut batch = vec![];
+    let mut batch_bytes = 0;
+
+    for item in items {
+        let size = size_of(&item);
+        if size > max_bytes {
+            oversized.push(item);
+            continue;
+        }
+        if !batch.is_empty() && (batch.len() >= max_count || batch_bytes + size > max_bytes) {
+            batches.push(std::mem::take(&mut batch));
+            batch_bytes = 0;
+        }
+        batch_bytes += size;
+        batch.push(item);
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+
+    (batches, oversized)
+}
+
+pub fn pack_entries(
+    entries: Vec<SendMessageBatchRequestEntry>,
+) -> (
+    Vec<Vec<SendMessageBatchRequestEntry>>,
+    Vec<SendMessageBatchRequestEntry>,
+) {
+    pack_by_size(
+        entries,
+        entry_size,
+        MAX_SQS_BATCH_SIZE,
+        MAX_SQS_BATCH_PAYLOAD_BYTES,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::batcher::{MAX_SQS_BATCH_PAYLOAD_BYTES, entry_size, pack_by_size, pack_entries};
+    use aws_sdk_sqs::types::{MessageAttributeValue, SendMessageBatchRequestEntry};
+
+    fn make_entry(id: &str, body_bytes: usize) -> SendMessageBatchRequestEntry {
+        SendMessageBatchRequestEntry::builder()
+            .id(id)
+            .message_body("x".repeat(body_bytes))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn should_pack_by_count() {
+        let (batches, oversized) = pack_by_size((0..25).collect(), |_| 1, 10, 100);
+
+        assert_eq!(
+            batches.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![10, 10, 5]
+        );
+        assert!(oversized.is_empty());
+    }
+
+    #[test]
+    fn should_pack_by_bytes_keeping_order() {
+        let sizes = vec![40, 40, 30, 50, 10, 100];
+
+        let (batches, oversized) = pack_by_size(sizes, |size| *size, 10, 100);
+
+        assert_eq!(batches, vec![vec![40, 40], vec![30, 50, 10], vec![100]]);
+        assert!(oversized.is_empty());
+    }
+
+    #[test]
+    fn should_separate_oversized_items() {
+        let (batches, oversized) = pack_by_size(vec![10, 101, 20], |size| *size, 10, 100);
+
+        assert_eq!(batches, vec![vec![10, 20]]);
+        assert_eq!(oversized, vec![101]);
+    }
+
+    #[test]
+    fn should_count_attributes_in_entry_size() {
+        let entry = SendMessageBatchRequestEntry::builder()
+            .id("1")
+            .message_body("body")
+            .message_attributes(
+                "changeKinds",
+                MessageAttributeValue::builder()
+                    .data_type("String")
+                    .string_value("Created")
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!(entry_size(&entry), 4 + 11 + 6 + 7);
+    }
+
+    #[test]
+    fn should_split_large_entries_into_multiple_batches() {
+        let entries = (0..4)
+            .map(|i| make_entry(&i.to_string(), 100 * 1024))
+            .collect::<Vec<_>>();
+
+        let (batches, oversized) = pack_entries(entries);
+
+        assert_eq!(batches.len(), 2);
+        assert!(batches.iter().all(|batch| {
+            batch.iter().map(entry_size).sum::<usize>() <= MAX_SQS_BATCH_PAYLOAD_BYTES
+        }));
+        assert!(oversized.is_empty());
+    }
+}
@@ -0,0 +1,341 @@
+use item_core::item_data::ItemData;
+use item_core::item_hash::{ItemHash, hash_item_details};
+use item_core::item_state::ItemState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum ItemField {
+    State,
+    Price,
+    Name,
+    Description,
+    Category,
+    Url,
+    ImageUrl,
+}
+
+/// What `scrape_and_push` compares when no fields are configured, same as `ItemData::hash()`.
+pub const DEFAULT_CHANGE_DETECTION_FIELDS: [ItemField; 2] = [ItemField::State, ItemField::Price];
+
+impl ItemField {
+    pub const ALL: [ItemField; 7] = [
+        ItemField::State,
+        ItemField::Price,
+        ItemField::Name,
+        ItemField::Description,
+        ItemField::Category,
+        ItemField::Url,
+        ItemField::ImageUrl,
+    ];
+
+    pub fn is_hashed_by_item_core(&self) -> bool {
+        matches!(self, ItemField::State | ItemField::Price)
+    }
+
+    fn value_hash(&self, item: &ItemData) -> String {
+        let value = match self {
+            ItemField::State => serde_json::to_value(&item.state),
+            ItemField::Price => serde_json::to_value(&item.price),
+            ItemField::Name => serde_json::to_value(&item.name),
+            ItemField::Description => serde_json::to_value(&item.description),
+            ItemField::Category => serde_json::to_value(&item.category),
+            ItemField::Url => serde_json::to_value(&item.url),
+            ItemField::ImageUrl => serde_json::to_value(&item.image_url),
+        }
+        .unwrap_or_default();
+        // serde_json::Value sorts object keys, so the multilingual maps hash deterministically
+        format!("{:016x}", fnv1a(value.to_string().as_bytes()))
+    }
+}
+
+impl Display for ItemField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ItemField::State => "state",
+            ItemField::Price => "price",
+            ItemField::Name => "name",
+            ItemField::Description => "description",
+            ItemField::Category => "category",
+            ItemField::Url => "url",
+            ItemField::ImageUrl => "imageUrl",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// FNV-1a, used because its output is stable across Rust versions unlike `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Item events only store item-core's hash, per-field hashes are only known for full items.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct ItemFingerprint {
+    pub hash: String,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub fields: HashMap<ItemField, String>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub state: Option<ItemState>,
+}
+
+impl ItemFingerprint {
+    pub fn from_hash(hash: String) -> Self {
+        ItemFingerprint {
+            hash,
+            fields: HashMap::new(),
+            state: None,
+        }
+    }
+
+    pub fn of(item: &ItemData) -> Self {
+        ItemFingerprint {
+            hash: item.hash(),
+            fields: ItemField::ALL
+                .iter()
+                .map(|field| (*field, field.value_hash(item)))
+                .collect(),
+            state: item.state.clone(),
+        }
+    }
+
+    /// `None` if `item` is unchanged in all `fields`. With only item-core's hash known, state
+    /// and price changes can't be told apart and are reported without fields.
+    pub fn changed_fields(&self, item: &ItemData, fields: &[ItemField]) -> Option<Vec<ItemField>> {
+        let mut hash_changed = false;
+        let changed_fields = fields
+            .iter()
+            .filter(|field| {
+                if let Some(old_hash) = self.fields.get(*field) {
+                    return *old_hash != field.value_hash(item);
+                }
+                match (field, &self.state) {
+                    (ItemField::State, Some(state)) => item.state.as_ref() != Some(state),
+                    // the stored hash is over the stored state and price
+                    (ItemField::Price, Some(state)) => {
+                        let price = item.price.as_ref().map(|price| price.amount);
+                        hash_item_details(Some(state.clone()), price) != self.hash
+                    }
+                    (field, None) if field.is_hashed_by_item_core() => {
+                        hash_changed |= self.hash != item.hash();
+                        false
+                    }
+                    _ => false,
+                }
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        (hash_changed || !changed_fields.is_empty()).then_some(changed_fields)
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ChangeKind {
+    Created,
+    StateChanged,
+    PriceChanged,
+    ContentChanged,
+    /// State or price changed, but the hash store can't tell which.
+    Changed,
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ItemDiff {
+    pub item: ItemData,
+
+    pub created: bool,
+
+    #[serde(rename = "changedFields")]
+    pub changed_fields: Vec<ItemField>,
+}
+
+impl ItemDiff {
+    pub fn created(item: ItemData) -> Self {
+        ItemDiff {
+            item,
+            created: true,
+            changed_fields: vec![],
+        }
+    }
+
+    pub fn changed(item: ItemData, changed_fields: Vec<ItemField>) -> Self {
+        ItemDiff {
+            item,
+            created: false,
+            changed_fields,
+        }
+    }
+
+    pub fn change_kinds(&self) -> Vec<ChangeKind> {
+        if self.created {
+            return vec![ChangeKind::Created];
+        }
+        if self.changed_fields.is_empty() {
+            return vec![ChangeKind::Changed];
+        }
+        let mut kinds = vec![];
+        if self.changed_fields.contains(&ItemField::State) {
+            kinds.push(ChangeKind::StateChanged);
+        }
+        if self.changed_fields.contains(&ItemField::Price) {
+            kinds.push(ChangeKind::PriceChanged);
+        }
+        if self
+            .changed_fields
+            .iter()
+            .any(|field| !field.is_hashed_by_item_core())
+        {
+            kinds.push(ChangeKind::ContentChanged);
+        }
+        kinds
+    }
+}
+
+pub fn detect_changes(
+    items: Vec<ItemData>,
+    item_id_fingerprint_map: &HashMap<String, ItemFingerprint>,
+    fields: &[ItemField],
+) -> Vec<ItemDiff> {
+    items
+        .into_iter()
+        .filter_map(
+            |item| match item_id_fingerprint_map.get(item.item_id.as_str()) {
+                Some(fingerprint) => fingerprint
+                    .changed_fields(&item, fields)
+                    .map(|changed_fields| ItemDiff::changed(item, changed_fields)),
+                None => Some(ItemDiff::created(item)),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::change_detection::{
+        ChangeKind, DEFAULT_CHANGE_DETECTION_FIELDS, ItemDiff, ItemField, ItemFingerprint,
+        detect_changes,
+    };
+    use item_core::item_data::ItemData;
+    use item_core::item_hash::hash_item_details;
+    use item_core::item_state::ItemState;
+    use item_core::item_state::ItemState::{AVAILABLE, SOLD};
+    use item_core::language::Language::EN;
+    use item_core::price::Currency::EUR;
+    use item_core::price::Price;
+    use std::collections::HashMap;
+
+    fn make_item() -> ItemData {
+        ItemData::new("foo#baz".to_string())
+            .state(AVAILABLE)
+            .price(Price::new(EUR, 42f32))
+            .url("https://foo.com/item=baz".to_string())
+            .clone()
+    }
+
+    #[test]
+    fn should_report_new_items_as_created() {
+        let actual = detect_changes(
+            vec![make_item()],
+            &HashMap::new(),
+            &DEFAULT_CHANGE_DETECTION_FIELDS,
+        );
+
+        assert_eq!(actual, vec![ItemDiff::created(make_item())]);
+        assert_eq!(actual[0].change_kinds(), vec![ChangeKind::Created]);
+    }
+
+    #[test]
+    fn should_behave_like_hash_comparison_for_hash_only_fingerprints() {
+        let unchanged = HashMap::from([(
+            "foo#baz".to_string(),
+            ItemFingerprint::from_hash(hash_item_details(Some(AVAILABLE), Some(42f32))),
+        )]);
+        let changed = HashMap::from([(
+            "foo#baz".to_string(),
+            ItemFingerprint::from_hash(hash_item_details(Some(SOLD), Some(42f32))),
+        )]);
+
+        let fields = DEFAULT_CHANGE_DETECTION_FIELDS;
+        assert!(detect_changes(vec![make_item()], &unchanged, &fields).is_empty());
+        let actual = detect_changes(vec![make_item()], &changed, &fields);
+        assert_eq!(actual, vec![ItemDiff::changed(make_item(), vec![])]);
+        assert_eq!(actual[0].change_kinds(), vec![ChangeKind::Changed]);
+    }
+
+    #[test]
+    fn should_tell_state_and_price_apart_with_stored_state() {
+        let fingerprint = |state: ItemState, price| ItemFingerprint {
+            state: Some(state.clone()),
+            ..ItemFingerprint::from_hash(hash_item_details(Some(state), price))
+        };
+        let price_changed =
+            HashMap::from([("foo#baz".to_string(), fingerprint(AVAILABLE, Some(40f32)))]);
+        let state_changed =
+            HashMap::from([("foo#baz".to_string(), fingerprint(SOLD, Some(42f32)))]);
+
+        assert_eq!(
+            detect_changes(
+                vec![make_item()],
+                &price_changed,
+                &DEFAULT_CHANGE_DETECTION_FIELDS
+            ),
+            vec![ItemDiff::changed(make_item(), vec![ItemField::Price])]
+        );
+        assert!(detect_changes(vec![make_item()], &price_changed, &[ItemField::State]).is_empty());
+        assert_eq!(
+            detect_changes(
+                vec![make_item()],
+                &state_changed,
+                &DEFAULT_CHANGE_DETECTION_FIELDS
+            ),
+            vec![ItemDiff::changed(make_item(), vec![ItemField::State])]
+        );
+    }
+
+    #[test]
+    fn should_detect_content_changes_with_full_fingerprints() {
+        let old = make_item();
+        let mut new = make_item();
+        new.name = HashMap::from([(EN, "Renamed".to_string())]);
+        let fingerprints = HashMap::from([("foo#baz".to_string(), ItemFingerprint::of(&old))]);
+
+        let actual = detect_changes(vec![new.clone()], &fingerprints, &ItemField::ALL);
+
+        assert_eq!(actual, vec![ItemDiff::changed(new, vec![ItemField::Name])]);
+        assert_eq!(actual[0].change_kinds(), vec![ChangeKind::ContentChanged]);
+    }
+
+    #[test]
+    fn should_ignore_fields_not_configured() {
+        let old = make_item();
+        let mut new = make_item();
+        new.category = Some("moved".to_string());
+        let fingerprints = HashMap::from([("foo#baz".to_string(), ItemFingerprint::of(&old))]);
+
+        let actual = detect_changes(vec![new], &fingerprints, &DEFAULT_CHANGE_DETECTION_FIELDS);
+
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn should_classify_price_and_state_changes() {
+        let diff = ItemDiff::changed(make_item(), vec![ItemField::State, ItemField::Price]);
+
+        assert_eq!(
+            diff.change_kinds(),
+            vec![ChangeKind::StateChanged, ChangeKind::PriceChanged]
+        );
+    }
+}
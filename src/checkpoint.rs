@@ -0,0 +1,87 @@
+use crate::pagination::PageToken;
+use crate::report::ScrapeReport;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
+
+/// Stop scraping this long before the Lambda deadline to leave time for pushing.
+pub const DEFAULT_CHECKPOINT_MARGIN_MILLIS: u64 = 60_000;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Checkpoint {
+    #[serde(rename = "nextPage")]
+    pub next_page: PageToken,
+
+    pub invocations: u32,
+
+    #[serde(rename = "pagesScraped")]
+    pub pages_scraped: usize,
+
+    #[serde(rename = "itemsScraped")]
+    pub items_scraped: usize,
+
+    #[serde(rename = "itemsPushed")]
+    pub items_pushed: usize,
+}
+
+impl Checkpoint {
+    pub fn next(
+        previous: Option<&Checkpoint>,
+        next_page: PageToken,
+        report: &ScrapeReport,
+    ) -> Self {
+        let mut checkpoint = previous.cloned().unwrap_or(Checkpoint {
+            next_page: next_page.clone(),
+            invocations: 0,
+            pages_scraped: 0,
+            items_scraped: 0,
+            items_pushed: 0,
+        });
+        checkpoint.next_page = next_page;
+        checkpoint.invocations += 1;
+        checkpoint.pages_scraped += report.pages_scraped;
+        checkpoint.items_scraped += report.items_scraped;
+        checkpoint.items_pushed += report.items_pushed;
+        checkpoint
+    }
+}
+
+pub fn deadline_before(deadline_epoch_millis: u64, margin: Duration) -> Instant {
+    let now_epoch_millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_millis() as u64)
+        .unwrap_or_default();
+    let remaining = Duration::from_millis(deadline_epoch_millis.saturating_sub(now_epoch_millis));
+    Instant::now() + remaining.saturating_sub(margin)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checkpoint::Checkpoint;
+    use crate::pagination::PageToken;
+    use crate::report::ScrapeReport;
+
+    #[test]
+    fn should_accumulate_totals() {
+        let report = ScrapeReport {
+            pages_scraped: 2,
+            items_scraped: 40,
+            items_pushed: 5,
+            ..ScrapeReport::default()
+        };
+
+        let first = Checkpoint::next(None, PageToken::Number { page: 3 }, &report);
+        let second = Checkpoint::next(Some(&first), PageToken::Number { page: 5 }, &report);
+
+        assert_eq!(
+            second,
+            Checkpoint {
+                next_page: PageToken::Number { page: 5 },
+                invocations: 2,
+                pages_scraped: 4,
+                items_scraped: 80,
+                items_pushed: 10,
+            }
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::report::ScrapeReport;
+use serde::{Deserialize, Serialize};
+
+/// `maxItemErrorRatio` is checked after every page and stops pushing once exceeded, the other
+/// thresholds only once scraping ended.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct CircuitBreaker {
+    #[serde(
+        rename = "maxItemErrorRatio",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub max_item_error_ratio: Option<f64>,
+
+    /// Without it, the first failed page ends scraping. Only numbered and offset pages are skipped.
+    #[serde(
+        rename = "maxConsecutivePageFailures",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub max_consecutive_page_failures: Option<u32>,
+
+    #[serde(rename = "minItems", skip_serializing_if = "Option::is_none", default)]
+    pub min_items: Option<usize>,
+}
+
+impl CircuitBreaker {
+    pub fn new() -> Self {
+        CircuitBreaker::default()
+    }
+
+    /// `items_scraped` includes earlier invocations and is only given for complete runs.
+    pub fn check(
+        &self,
+        report: &ScrapeReport,
+        consecutive_page_failures: u32,
+        items_scraped: Option<usize>,
+    ) -> Option<String> {
+        if let Some(max) = self.max_consecutive_page_failures
+            && consecutive_page_failures >= max
+        {
+            return Some(format!(
+                "{} pages in a row failed",
+                consecutive_page_failures
+            ));
+        }
+
+        if let Some(reason) = self.check_item_errors(report) {
+            return Some(reason);
+        }
+
+        match (self.min_items, items_scraped) {
+            (Some(min_items), Some(items_scraped)) if items_scraped < min_items => Some(format!(
+                "Only {} items scraped, expected at least {}",
+                items_scraped, min_items
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn check_item_errors(&self, report: &ScrapeReport) -> Option<String> {
+        let max_ratio = self.max_item_error_ratio?;
+        let item_errors = report.item_errors.values().sum::<usize>();
+        let attempted = report.items_scraped + item_errors;
+        if attempted == 0 || item_errors as f64 / attempted as f64 <= max_ratio {
+            return None;
+        }
+        Some(format!(
+            "{} of {} items failed, more than the allowed ratio of {}",
+            item_errors, attempted, max_ratio
+        ))
+    }
+
+    // region fluent_setter
+
+    pub fn max_item_error_ratio(&mut self, max_item_error_ratio: f64) -> &mut Self {
+        self.max_item_error_ratio = Some(max_item_error_ratio);
+        self
+    }
+
+    pub fn max_consecutive_page_failures(
+        &mut self,
+        max_consecutive_page_failures: u32,
+    ) -> &mut Self {
+        self.max_consecutive_page_failures = Some(max_consecutive_page_failures);
+        self
+    }
+
+    pub fn min_items(&mut self, min_items: usize) -> &mut Self {
+        self.min_items = Some(min_items);
+        self
+    }
+
+    // endregion
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circuit_breaker::CircuitBreaker;
+    use crate::report::ScrapeReport;
+    use std::collections::HashMap;
+
+    fn report(items_scraped: usize, item_errors: usize) -> ScrapeReport {
+        ScrapeReport {
+            items_scraped,
+            item_errors: HashMap::from([("Parse".to_string(), item_errors)]),
+            ..ScrapeReport::default()
+        }
+    }
+
+    #[test]
+    fn should_trip_on_item_error_ratio() {
+        let circuit_breaker = CircuitBreaker::new().max_item_error_ratio(0.5).clone();
+
+        assert_eq!(circuit_breaker.check(&report(5, 5), 0, None), None);
+        assert!(circuit_breaker.check(&report(4, 6), 0, None).is_some());
+    }
+
+    #[test]
+    fn should_trip_on_consecutive_page_failures() {
+        let circuit_breaker = CircuitBreaker::new()
+            .max_consecutive_page_failures(3)
+            .clone();
+
+        assert_eq!(circuit_breaker.check(&report(10, 0), 2, None), None);
+        assert!(circuit_breaker.check(&report(10, 0), 3, None).is_some());
+    }
+
+    #[test]
+    fn should_trip_on_too_few_items_of_complete_runs_only() {
+        let circuit_breaker = CircuitBreaker::new().min_items(100).clone();
+
+        assert_eq!(circuit_breaker.check(&report(10, 0), 0, None), None);
+        assert!(circuit_breaker.check(&report(10, 0), 0, Some(10)).is_some());
+        assert_eq!(circuit_breaker.check(&report(10, 0), 0, Some(110)), None);
+    }
+}
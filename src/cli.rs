@@ -0,0 +1,379 @@
+//! Runs a [`Scraper`] locally instead of as Lambda. Requires the `cli` feature.
+//!
+//! A scraper crate only needs a binary like:
+//!
+//! ```ignore
+//! #[tokio::main]
+//! async fn main() -> Result<(), scrape::cli::CliError> {
+//!     scrape::cli::run_cli::<MyScraper>().await
+//! }
+//! ```
+
+use crate::change_detection::ItemDiff;
+use crate::hash_store::{HashStore, InMemoryHashStore, JsonFileHashStore};
+use crate::report::{PushResult, ScrapeReport};
+use crate::scraper::Scraper;
+use crate::scraper_config::ScraperConfig;
+use crate::sink::{InMemorySink, ItemSink, JsonLinesFileSink, WebhookSink};
+use crate::{ScrapePushError, scrape_and_push};
+use async_trait::async_trait;
+use clap::{Parser, ValueEnum};
+use futures::StreamExt;
+use item_core::item_data::ItemData;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt::Display;
+use std::path::PathBuf;
+use tracing::warn;
+
+#[derive(ValueEnum, PartialEq, Debug, Clone, Copy)]
+pub enum OutputFormat {
+    Json,
+    Jsonl,
+    Table,
+}
+
+#[derive(Parser, PartialEq, Debug)]
+#[command(about = "Runs a scraper locally and prints what it scraped.")]
+pub struct CliArgs {
+    /// ScraperConfig as JSON file.
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+
+    /// Base URL of the shop, overrides the one of --config.
+    #[arg(long, required_unless_present = "config")]
+    pub base_url: Option<String>,
+
+    /// Stops scraping after this many pages.
+    #[arg(long)]
+    pub max_pages: Option<usize>,
+
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::Jsonl)]
+    pub format: OutputFormat,
+
+    /// Prints the scraped items as they are, without diffing them.
+    #[arg(long)]
+    pub items: bool,
+
+    /// JSON file of item fingerprints to diff against, updated with the diffs of this run.
+    /// Without it, every item is new.
+    #[arg(long)]
+    pub hash_store: Option<PathBuf>,
+
+    /// Pushes diffs as JSON array to this URL.
+    #[arg(long, conflicts_with = "no_push")]
+    pub webhook: Option<String>,
+
+    /// Writes diffs as JSON lines to this file, replacing it.
+    #[arg(long, conflicts_with = "no_push")]
+    pub jsonl: Option<PathBuf>,
+
+    /// Dry run: pushes nothing and doesn't update --hash-store, only prints.
+    #[arg(long)]
+    pub no_push: bool,
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    IoError(std::io::Error),
+    SerdeError(serde_json::Error),
+    ScrapePushError(ScrapePushError),
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::IoError(err) => write!(f, "IoError error: {}", err),
+            CliError::SerdeError(err) => write!(f, "SerdeError error: {}", err),
+            CliError::ScrapePushError(err) => write!(f, "ScrapePushError error: {}", err),
+        }
+    }
+}
+
+impl Error for CliError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CliError::IoError(err) => Some(err),
+            CliError::SerdeError(err) => Some(err),
+            CliError::ScrapePushError(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(err: std::io::Error) -> Self {
+        CliError::IoError(err)
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(err: serde_json::Error) -> Self {
+        CliError::SerdeError(err)
+    }
+}
+
+impl From<ScrapePushError> for CliError {
+    fn from(err: ScrapePushError) -> Self {
+        CliError::ScrapePushError(err)
+    }
+}
+
+impl CliArgs {
+    pub async fn scraper_config(&self) -> Result<ScraperConfig, CliError> {
+        let mut scraper_config = match &self.config {
+            Some(path) => serde_json::from_slice(&tokio::fs::read(path).await?)?,
+            None => ScraperConfig::new(String::new()),
+        };
+        if let Some(base_url) = &self.base_url {
+            scraper_config.base_url(base_url.clone());
+        }
+        if let Some(max_pages) = self.max_pages {
+            scraper_config.max_pages(max_pages);
+        }
+        if self.no_push {
+            scraper_config.dry_run(true);
+        }
+        Ok(scraper_config)
+    }
+}
+
+pub async fn run_cli<T>() -> Result<(), CliError>
+where
+    T: TryFrom<ScraperConfig> + Scraper,
+    T::Error: Display,
+{
+    run_cli_with_args::<T>(CliArgs::parse()).await
+}
+
+pub async fn run_cli_with_args<T>(args: CliArgs) -> Result<(), CliError>
+where
+    T: TryFrom<ScraperConfig> + Scraper,
+    T::Error: Display,
+{
+    let scraper_config = args.scraper_config().await?;
+    let scraper = T::try_from(scraper_config.clone())
+        .map_err(|e| ScrapePushError::InvalidScraperConfig(e.to_string()))?;
+    let reqwest_client = reqwest::Client::new();
+
+    if args.items {
+        let items = scraper
+            .scrape(&reqwest_client, &scraper_config)
+            .filter_map(|item_result| async move {
+                item_result
+                    .inspect_err(|e| warn!(error = %e, "Scraping failed."))
+                    .ok()
+            })
+            .collect::<Vec<_>>()
+            .await;
+        print!("{}", render(&items, args.format, item_row, ITEM_HEADERS)?);
+        return Ok(());
+    }
+
+    let target: Option<Box<dyn ItemSink>> = match (&args.webhook, &args.jsonl) {
+        _ if args.no_push => None,
+        (Some(url), _) => Some(Box::new(WebhookSink::new(
+            reqwest_client.clone(),
+            url.clone(),
+        ))),
+        (None, Some(path)) => Some(Box::new(JsonLinesFileSink::create(path).await?)),
+        (None, None) => None,
+    };
+    let sink = CollectingSink {
+        target,
+        collected: InMemorySink::new(),
+    };
+    let hash_store: Box<dyn HashStore> = match &args.hash_store {
+        Some(path) => Box::new(JsonFileHashStore::new(path)),
+        None => Box::new(InMemoryHashStore::new()),
+    };
+
+    let mut report = scrape_and_push(
+        &scraper,
+        &scraper_config,
+        &reqwest_client,
+        &sink,
+        hash_store.as_ref(),
+    )
+    .await?;
+    let diffs = if args.no_push {
+        std::mem::take(&mut report.would_push)
+    } else {
+        sink.collected.diffs()
+    };
+    print!("{}", render(&diffs, args.format, diff_row, DIFF_HEADERS)?);
+    eprintln!("{}", render_report(&report)?);
+    Ok(())
+}
+
+struct CollectingSink {
+    target: Option<Box<dyn ItemSink>>,
+    collected: InMemorySink,
+}
+
+#[async_trait]
+impl ItemSink for CollectingSink {
+    async fn push(&self, diffs: Vec<ItemDiff>, scraper_config: &ScraperConfig) -> PushResult {
+        let Some(target) = &self.target else {
+            return self.collected.push(diffs, scraper_config).await;
+        };
+        let push_result = target.push(diffs.clone(), scraper_config).await;
+        let pushed = diffs
+            .into_iter()
+            .filter(|diff| {
+                !push_result
+                    .failures
+                    .iter()
+                    .any(|failure| failure.item_id == diff.item.item_id)
+            })
+            .collect();
+        self.collected.push(pushed, scraper_config).await;
+        push_result
+    }
+}
+
+const ITEM_HEADERS: &[&str] = &["itemId", "state", "price", "name"];
+
+const DIFF_HEADERS: &[&str] = &["itemId", "changeKinds", "changedFields", "state", "price"];
+
+fn optional_json(value: &impl Serialize) -> String {
+    match serde_json::to_value(value).unwrap_or_default() {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(string) => string,
+        value => value.to_string(),
+    }
+}
+
+fn item_row(item: &ItemData) -> Vec<String> {
+    let mut names = item.name.iter().collect::<Vec<_>>();
+    names.sort_by_key(|(language, _)| format!("{:?}", language));
+    vec![
+        item.item_id.clone(),
+        optional_json(&item.state),
+        optional_json(&item.price),
+        names
+            .first()
+            .map(|(_, name)| name.to_string())
+            .unwrap_or_default(),
+    ]
+}
+
+fn diff_row(diff: &ItemDiff) -> Vec<String> {
+    let join = |values: Vec<String>| values.join(",");
+    vec![
+        diff.item.item_id.clone(),
+        join(
+            diff.change_kinds()
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        ),
+        join(
+            diff.changed_fields
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        ),
+        optional_json(&diff.item.state),
+        optional_json(&diff.item.price),
+    ]
+}
+
+fn render<T: Serialize>(
+    values: &[T],
+    format: OutputFormat,
+    row: fn(&T) -> Vec<String>,
+    headers: &[&str],
+) -> Result<String, CliError> {
+    Ok(match format {
+        OutputFormat::Json => format!("{}\n", serde_json::to_string_pretty(values)?),
+        OutputFormat::Jsonl => values
+            .iter()
+            .map(|value| serde_json::to_string(value).map(|line| line + "\n"))
+            .collect::<Result<String, _>>()?,
+        OutputFormat::Table => render_table(headers, values.iter().map(row).collect()),
+    })
+}
+
+fn render_table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let widths = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain([header.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    let render_row = |cells: Vec<String>| {
+        let line = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        format!("{}\n", line.trim_end())
+    };
+
+    let mut table = render_row(headers.iter().map(ToString::to_string).collect());
+    for row in rows {
+        table.push_str(&render_row(row));
+    }
+    table
+}
+
+fn render_report(report: &ScrapeReport) -> Result<String, CliError> {
+    Ok(serde_json::to_string_pretty(report)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{CliArgs, OutputFormat, render_table};
+    use clap::Parser;
+
+    #[tokio::test]
+    async fn should_override_config_with_flags() {
+        let args = CliArgs::try_parse_from([
+            "scrape",
+            "--base-url",
+            "https://foo.bar",
+            "--max-pages",
+            "2",
+            "--format",
+            "table",
+        ])
+        .unwrap();
+
+        let scraper_config = args.scraper_config().await.unwrap();
+
+        assert_eq!(args.format, OutputFormat::Table);
+        assert_eq!(scraper_config.base_url, "https://foo.bar");
+        assert_eq!(scraper_config.max_pages, Some(2));
+    }
+
+    #[test]
+    fn should_require_config_or_base_url() {
+        assert!(CliArgs::try_parse_from(["scrape"]).is_err());
+        assert!(
+            CliArgs::try_parse_from(["scrape", "--webhook", "https://foo.bar", "--no-push"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn should_align_table_columns() {
+        let table = render_table(
+            &["itemId", "state"],
+            vec![
+                vec!["foo#1".to_string(), "AVAILABLE".to_string()],
+                vec!["foo#12345678".to_string(), String::new()],
+            ],
+        );
+
+        assert_eq!(
+            table,
+            "itemId        state\nfoo#1         AVAILABLE\nfoo#12345678\n"
+        );
+    }
+}
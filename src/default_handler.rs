@@ -1,48 +1,73 @@
+use crate::checkpoint::{DEFAULT_CHECKPOINT_MARGIN_MILLIS, deadline_before};
+use crate::hash_store::HashStore;
+use crate::report::ScrapeReport;
 use crate::scraper::Scraper;
 use crate::scraper_config::ScraperConfig;
-use crate::{ScrapePushError, scrape_and_push};
+use crate::sink::ItemSink;
+use crate::{ScrapePushError, scrape_and_push_until};
 use lambda_runtime::LambdaEvent;
+use std::fmt::Display;
+use std::time::Duration;
 use tracing::{error, info};
 
+/// Invoking the handler with the response's `continuation` config resumes a stopped run.
 #[tracing::instrument(
-    skip(event, reqwest_client, sqs_client, dynamodb_client),
+    skip(event, reqwest_client, sink, hash_store),
     fields(req_id = %event.context.request_id))
 ]
 pub async fn default_function_handler<T>(
     event: LambdaEvent<ScraperConfig>,
     reqwest_client: &reqwest::Client,
-    sqs_client: &aws_sdk_sqs::Client,
-    dynamodb_client: &aws_sdk_dynamodb::Client,
-    item_write_lambda_q_url: &str,
-) -> Result<(), ScrapePushError>
+    sink: &dyn ItemSink,
+    hash_store: &dyn HashStore,
+) -> Result<ScrapeReport, ScrapePushError>
 where
-    T: From<ScraperConfig> + Scraper,
+    T: TryFrom<ScraperConfig> + Scraper,
+    T::Error: Display,
 {
     let scraper_cfg = event.payload;
-    let scraper: T = scraper_cfg.clone().into();
+    let deadline = deadline_before(
+        event.context.deadline,
+        Duration::from_millis(
+            scraper_cfg
+                .checkpoint_margin_millis
+                .unwrap_or(DEFAULT_CHECKPOINT_MARGIN_MILLIS),
+        ),
+    );
     info!(
         scraperConfig = serde_json::to_string_pretty(&scraper_cfg)
             .expect("shouldn't fail serializing ScraperConfig"),
         "Handler invoked."
     );
+    let scraper = match T::try_from(scraper_cfg.clone()) {
+        Ok(scraper) => scraper,
+        Err(e) => {
+            error!(error = %e, "Handler failed building scraper.");
+            return Err(ScrapePushError::InvalidScraperConfig(e.to_string()));
+        }
+    };
 
-    let res = scrape_and_push(
+    let res = scrape_and_push_until(
         &scraper,
         &scraper_cfg,
         reqwest_client,
-        sqs_client,
-        dynamodb_client,
-        item_write_lambda_q_url,
+        sink,
+        hash_store,
+        Some(deadline),
     )
     .await;
 
     match res {
-        Ok(total_sent_count) => {
-            info!(total = total_sent_count, "Handler finished.");
-            Ok(())
+        Ok(report) => {
+            info!(
+                total = report.items_pushed,
+                report = ?report,
+                "Handler finished."
+            );
+            Ok(report)
         }
         Err(e) => {
-            error!(error = %e,"Handler failed.");
+            error!(error = %e, "Handler failed.");
             Err(e)
         }
     }
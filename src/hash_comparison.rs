@@ -2,6 +2,7 @@ use item_core::item_data::ItemData;
 use item_core::item_hash::ItemHash;
 use std::collections::HashMap;
 
+#[deprecated(note = "use `change_detection::detect_changes` instead")]
 pub fn drop_unchanged_diffs(diffs: &mut Vec<ItemData>, item_id_hash_map: &HashMap<String, String>) {
     diffs.retain(|diff| {
         let old_hash = item_id_hash_map.get(diff.item_id.as_str());
@@ -10,12 +11,13 @@ pub fn drop_unchanged_diffs(diffs: &mut Vec<ItemData>, item_id_hash_map: &HashMa
                 let new_hash = &diff.hash();
                 old_hash.ne(new_hash)
             }
-            None => return true,
+            None => true,
         }
     })
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use crate::hash_comparison::drop_unchanged_diffs;
     use item_core::item_data::ItemData;
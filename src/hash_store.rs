@@ -0,0 +1,473 @@
+use crate::change_detection::ItemFingerprint;
+use async_trait::async_trait;
+use aws_sdk_dynamodb::operation::query::QueryError;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_sqs::config::http::HttpResponse;
+use aws_sdk_sqs::error::SdkError;
+use item_core::item_data::ItemData;
+use item_core::item_state::ItemState;
+use item_read::item_hash::get_latest_item_event_hash_map_by_source_id;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum HashStoreError {
+    QueryItemEventHashesError(SdkError<QueryError, HttpResponse>),
+    IoError(std::io::Error),
+    SerdeError(serde_json::Error),
+}
+
+impl Display for HashStoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashStoreError::QueryItemEventHashesError(err) => {
+                write!(f, "QueryItemEventHashesError error: {}", err)
+            }
+            HashStoreError::IoError(err) => write!(f, "IoError error: {}", err),
+            HashStoreError::SerdeError(err) => write!(f, "SerdeError error: {}", err),
+        }
+    }
+}
+
+impl Error for HashStoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HashStoreError::QueryItemEventHashesError(err) => Some(err),
+            HashStoreError::IoError(err) => Some(err),
+            HashStoreError::SerdeError(err) => Some(err),
+        }
+    }
+}
+
+impl From<SdkError<QueryError, HttpResponse>> for HashStoreError {
+    fn from(err: SdkError<QueryError, HttpResponse>) -> Self {
+        HashStoreError::QueryItemEventHashesError(err)
+    }
+}
+
+impl From<std::io::Error> for HashStoreError {
+    fn from(err: std::io::Error) -> Self {
+        HashStoreError::IoError(err)
+    }
+}
+
+impl From<serde_json::Error> for HashStoreError {
+    fn from(err: serde_json::Error) -> Self {
+        HashStoreError::SerdeError(err)
+    }
+}
+
+impl HashStoreError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            HashStoreError::QueryItemEventHashesError(_) => "QueryItemEventHashesError",
+            HashStoreError::IoError(_) => "IoError",
+            HashStoreError::SerdeError(_) => "SerdeError",
+        }
+    }
+}
+
+#[async_trait]
+pub trait HashStore: Send + Sync {
+    async fn load(
+        &self,
+        source_id: &str,
+    ) -> Result<HashMap<String, ItemFingerprint>, HashStoreError>;
+
+    /// Stores fed by the pushed items themselves, like item events in DynamoDB, ignore this.
+    async fn record(&self, _source_id: &str, _items: &[ItemData]) -> Result<(), HashStoreError> {
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), HashStoreError> {
+        Ok(())
+    }
+
+    /// Whether loaded fingerprints know more fields than item-core's hash over state and price.
+    fn has_field_fingerprints(&self) -> bool {
+        false
+    }
+
+    /// Whether loaded fingerprints know the stored state, which tells state and price apart.
+    fn has_states(&self) -> bool {
+        false
+    }
+}
+
+/// Where the item-write Lambda stores item events, queried for the latest state of every item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemEventTable {
+    pub table_name: String,
+    /// Index with `source_id` as partition key.
+    pub source_id_index: String,
+}
+
+impl Default for ItemEventTable {
+    fn default() -> Self {
+        ItemEventTable {
+            table_name: "items".to_string(),
+            source_id_index: "source_id-index".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DynamoDbHashStore {
+    dynamodb_client: aws_sdk_dynamodb::Client,
+    item_event_table: ItemEventTable,
+}
+
+impl DynamoDbHashStore {
+    pub fn new(dynamodb_client: aws_sdk_dynamodb::Client) -> Self {
+        DynamoDbHashStore {
+            dynamodb_client,
+            item_event_table: ItemEventTable::default(),
+        }
+    }
+
+    pub fn item_event_table(&mut self, item_event_table: ItemEventTable) -> &mut Self {
+        self.item_event_table = item_event_table;
+        self
+    }
+
+    async fn load_latest_states(
+        &self,
+        source_id: &str,
+    ) -> Result<HashMap<String, ItemState>, HashStoreError> {
+        let mut item_events = vec![];
+        let mut exclusive_start_key = None;
+        loop {
+            let output = self
+                .dynamodb_client
+                .query()
+                .table_name(&self.item_event_table.table_name)
+                .index_name(&self.item_event_table.source_id_index)
+                .key_condition_expression("source_id = :source_id")
+                .expression_attribute_values(":source_id", AttributeValue::S(source_id.to_string()))
+                // `state` is a reserved word
+                .expression_attribute_names("#state", "state")
+                .projection_expression("item_id, #state, created")
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+            item_events.extend(output.items().iter().cloned());
+            exclusive_start_key = output.last_evaluated_key().cloned();
+            if exclusive_start_key.is_none() {
+                return Ok(latest_states(&item_events));
+            }
+        }
+    }
+}
+
+/// The state of the latest event of every item, by `created`.
+fn latest_states(item_events: &[HashMap<String, AttributeValue>]) -> HashMap<String, ItemState> {
+    let text = |item_event: &HashMap<String, AttributeValue>, name: &str| {
+        item_event
+            .get(name)
+            .and_then(|value| value.as_s().ok())
+            .cloned()
+    };
+    let mut latest: HashMap<String, (String, Option<ItemState>)> = HashMap::new();
+    for item_event in item_events {
+        let Some(item_id) = text(item_event, "item_id") else {
+            continue;
+        };
+        let created = text(item_event, "created").unwrap_or_default();
+        let state = text(item_event, "state")
+            .and_then(|state| serde_json::from_value(serde_json::Value::String(state)).ok());
+        match latest.get(&item_id) {
+            Some((latest_created, _)) if *latest_created >= created => {}
+            _ => {
+                latest.insert(item_id, (created, state));
+            }
+        }
+    }
+    latest
+        .into_iter()
+        .filter_map(|(item_id, (_, state))| Some((item_id, state?)))
+        .collect()
+}
+
+#[async_trait]
+impl HashStore for DynamoDbHashStore {
+    async fn load(
+        &self,
+        source_id: &str,
+    ) -> Result<HashMap<String, ItemFingerprint>, HashStoreError> {
+        let mut states = self.load_latest_states(source_id).await?;
+        Ok(
+            get_latest_item_event_hash_map_by_source_id(source_id, &self.dynamodb_client)
+                .await?
+                .into_iter()
+                .map(|(item_id, hash)| {
+                    let fingerprint = ItemFingerprint {
+                        state: states.remove(&item_id),
+                        ..ItemFingerprint::from_hash(hash)
+                    };
+                    (item_id, fingerprint)
+                })
+                .collect(),
+        )
+    }
+
+    fn has_states(&self) -> bool {
+        true
+    }
+}
+
+type SourceFingerprints = HashMap<String, HashMap<String, ItemFingerprint>>;
+
+fn record_items(fingerprints: &mut SourceFingerprints, source_id: &str, items: &[ItemData]) {
+    fingerprints
+        .entry(source_id.to_string())
+        .or_default()
+        .extend(
+            items
+                .iter()
+                .map(|item| (item.item_id.clone(), ItemFingerprint::of(item))),
+        );
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryHashStore {
+    fingerprints: std::sync::Mutex<SourceFingerprints>,
+}
+
+impl InMemoryHashStore {
+    pub fn new() -> Self {
+        InMemoryHashStore::default()
+    }
+
+    pub fn with_items(source_id: &str, items: &[ItemData]) -> Self {
+        let store = InMemoryHashStore::new();
+        record_items(&mut store.fingerprints.lock().unwrap(), source_id, items);
+        store
+    }
+
+    pub fn with_fingerprints(
+        source_id: &str,
+        fingerprints: HashMap<String, ItemFingerprint>,
+    ) -> Self {
+        InMemoryHashStore {
+            fingerprints: std::sync::Mutex::new(HashMap::from([(
+                source_id.to_string(),
+                fingerprints,
+            )])),
+        }
+    }
+}
+
+#[async_trait]
+impl HashStore for InMemoryHashStore {
+    async fn load(
+        &self,
+        source_id: &str,
+    ) -> Result<HashMap<String, ItemFingerprint>, HashStoreError> {
+        Ok(self
+            .fingerprints
+            .lock()
+            .unwrap()
+            .get(source_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn record(&self, source_id: &str, items: &[ItemData]) -> Result<(), HashStoreError> {
+        record_items(&mut self.fingerprints.lock().unwrap(), source_id, items);
+        Ok(())
+    }
+
+    fn has_field_fingerprints(&self) -> bool {
+        true
+    }
+
+    fn has_states(&self) -> bool {
+        true
+    }
+}
+
+/// Recorded items are only written by [`HashStore::flush`].
+#[derive(Debug)]
+pub struct JsonFileHashStore {
+    path: PathBuf,
+    // the whole file, read on first use
+    fingerprints: tokio::sync::Mutex<Option<SourceFingerprints>>,
+}
+
+impl JsonFileHashStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        JsonFileHashStore {
+            path: path.into(),
+            fingerprints: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    async fn read(&self) -> Result<SourceFingerprints, HashStoreError> {
+        match tokio::fs::read(&self.path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl HashStore for JsonFileHashStore {
+    async fn load(
+        &self,
+        source_id: &str,
+    ) -> Result<HashMap<String, ItemFingerprint>, HashStoreError> {
+        let mut fingerprints = self.fingerprints.lock().await;
+        if fingerprints.is_none() {
+            *fingerprints = Some(self.read().await?);
+        }
+        Ok(fingerprints
+            .as_ref()
+            .and_then(|fingerprints| fingerprints.get(source_id))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn record(&self, source_id: &str, items: &[ItemData]) -> Result<(), HashStoreError> {
+        let mut fingerprints = self.fingerprints.lock().await;
+        if fingerprints.is_none() {
+            *fingerprints = Some(self.read().await?);
+        }
+        if let Some(fingerprints) = fingerprints.as_mut() {
+            record_items(fingerprints, source_id, items);
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), HashStoreError> {
+        let fingerprints = self.fingerprints.lock().await;
+        let Some(fingerprints) = fingerprints.as_ref() else {
+            return Ok(());
+        };
+        // renamed over the file, so an interrupted write never leaves it truncated
+        let tmp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec(fingerprints)?).await?;
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+        Ok(())
+    }
+
+    fn has_field_fingerprints(&self) -> bool {
+        true
+    }
+
+    fn has_states(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hash_store::{HashStore, InMemoryHashStore, JsonFileHashStore, latest_states};
+    use aws_sdk_dynamodb::types::AttributeValue;
+    use futures::future::join_all;
+    use item_core::item_data::ItemData;
+    use item_core::item_hash::ItemHash;
+    use item_core::item_state::ItemState::{AVAILABLE, REMOVED};
+    use std::collections::HashMap;
+    use test_api::generator::Generator;
+
+    #[tokio::test]
+    async fn should_load_recorded_items_by_source() {
+        let store = InMemoryHashStore::new();
+        let items = ItemData::generate_many(2);
+
+        store.record("https://foo.bar", &items).await.unwrap();
+
+        let fingerprints = store.load("https://foo.bar").await.unwrap();
+        assert_eq!(fingerprints.len(), 2);
+        assert_eq!(fingerprints[&items[0].item_id].hash, items[0].hash());
+        assert!(store.load("https://baz.bar").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_persist_fingerprints_in_file() {
+        let path =
+            std::env::temp_dir().join(format!("hash-store-test-{}.json", uuid::Uuid::new_v4()));
+        let items = ItemData::generate_many(3);
+
+        let store = JsonFileHashStore::new(&path);
+        store.record("https://foo.bar", &items).await.unwrap();
+        store.flush().await.unwrap();
+        let fingerprints = JsonFileHashStore::new(&path)
+            .load("https://foo.bar")
+            .await
+            .unwrap();
+
+        let _ = tokio::fs::remove_file(&path).await;
+        assert_eq!(fingerprints.len(), 3);
+        assert!(!fingerprints[&items[2].item_id].fields.is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_keep_items_of_concurrent_records() {
+        let path =
+            std::env::temp_dir().join(format!("hash-store-test-{}.json", uuid::Uuid::new_v4()));
+        let store = JsonFileHashStore::new(&path);
+        let batches = (0..5)
+            .map(|_| ItemData::generate_many(10))
+            .collect::<Vec<_>>();
+
+        join_all(
+            batches
+                .iter()
+                .map(|items| store.record("https://foo.bar", items)),
+        )
+        .await;
+        store.flush().await.unwrap();
+        let fingerprints = JsonFileHashStore::new(&path)
+            .load("https://foo.bar")
+            .await
+            .unwrap();
+
+        let _ = tokio::fs::remove_file(&path).await;
+        assert_eq!(fingerprints.len(), 50);
+    }
+
+    #[tokio::test]
+    async fn should_treat_missing_file_as_empty() {
+        let store = JsonFileHashStore::new(std::env::temp_dir().join("does-not-exist.json"));
+
+        assert!(store.load("https://foo.bar").await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_take_state_of_latest_item_event() {
+        let item_event = |item_id: &str, created: &str, state: &str| {
+            HashMap::from([
+                (
+                    "item_id".to_string(),
+                    AttributeValue::S(item_id.to_string()),
+                ),
+                (
+                    "created".to_string(),
+                    AttributeValue::S(created.to_string()),
+                ),
+                ("state".to_string(), AttributeValue::S(state.to_string())),
+            ])
+        };
+        let item_events = vec![
+            item_event("https://foo.bar#1", "2025-01-02T00:00:00Z", "REMOVED"),
+            item_event("https://foo.bar#1", "2025-01-01T00:00:00Z", "AVAILABLE"),
+            item_event("https://foo.bar#2", "2025-01-01T00:00:00Z", "REMOVED"),
+            item_event("https://foo.bar#2", "2025-01-03T00:00:00Z", "AVAILABLE"),
+            item_event("https://foo.bar#3", "2025-01-01T00:00:00Z", "UNKNOWN"),
+        ];
+
+        let actual = latest_states(&item_events);
+
+        assert_eq!(
+            actual,
+            HashMap::from([
+                ("https://foo.bar#1".to_string(), REMOVED),
+                ("https://foo.bar#2".to_string(), AVAILABLE),
+            ])
+        );
+    }
+}
@@ -0,0 +1,595 @@
+use crate::pagination::{Page, PageToken};
+use crate::price_parser::parse_price;
+use crate::scrape_client::ScrapeClient;
+use crate::scraper::{ScrapeError, Scraper};
+use crate::scraper_config::{ScraperConfig, ScraperConfigError};
+use crate::state_classifier::StateClassifier;
+use async_trait::async_trait;
+use item_core::item_data::ItemData;
+use item_core::item_state::ItemState;
+use item_core::language::Language;
+use item_core::price::{Currency, Price};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+}
+
+/// Query parameters rendering to an empty string, like `{cursor}` of the first page, are left out.
+/// A body string consisting of a single numeric variable is sent as number.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct RequestTemplate {
+    #[serde(default)]
+    pub method: HttpMethod,
+
+    pub url: String,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub query: HashMap<String, String>,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub headers: HashMap<String, String>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub body: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum JsonApiPagination {
+    Page {
+        #[serde(rename = "firstPage", default = "first_page_number")]
+        first_page: i16,
+    },
+    Offset {
+        limit: u64,
+    },
+    Cursor {
+        #[serde(rename = "nextCursorPointer")]
+        next_cursor_pointer: String,
+    },
+}
+
+fn first_page_number() -> i16 {
+    1
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct JsonFields {
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub description: Option<String>,
+
+    /// Either a number or a price text like `"12,50 €"`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub price: Option<String>,
+
+    /// An ISO 4217 code, falls back to the currency of the `ScraperConfig`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub currency: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub state: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub category: Option<String>,
+
+    #[serde(rename = "imageUrl", skip_serializing_if = "Option::is_none", default)]
+    pub image_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct JsonApiScraperConfig {
+    pub request: RequestTemplate,
+
+    pub pagination: JsonApiPagination,
+
+    /// JSON pointer to the item array of a response. Empty for a top-level array.
+    #[serde(rename = "itemsPointer", default)]
+    pub items_pointer: String,
+
+    pub fields: JsonFields,
+
+    /// Takes precedence over `stateLabels` and the built-in labels.
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub states: HashMap<String, ItemState>,
+
+    #[serde(
+        rename = "defaultState",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub default_state: Option<ItemState>,
+}
+
+#[derive(Debug)]
+pub struct JsonApiScraper {
+    base_url: String,
+    config: JsonApiScraperConfig,
+    states: StateClassifier,
+    currency: Option<Currency>,
+    language: Option<Language>,
+}
+
+fn check_pointer(pointer: &str) -> Result<(), ScraperConfigError> {
+    if pointer.is_empty() || pointer.starts_with('/') {
+        Ok(())
+    } else {
+        Err(ScraperConfigError::InvalidPointer {
+            pointer: pointer.to_string(),
+        })
+    }
+}
+
+impl TryFrom<ScraperConfig> for JsonApiScraper {
+    type Error = ScraperConfigError;
+
+    fn try_from(scraper_config: ScraperConfig) -> Result<Self, Self::Error> {
+        let mut states = StateClassifier::for_config(&scraper_config);
+        let config = scraper_config
+            .json_api_scraper
+            .ok_or(ScraperConfigError::Missing {
+                field: "jsonApiScraper",
+            })?;
+
+        let fields = &config.fields;
+        check_pointer(&config.items_pointer)?;
+        check_pointer(&fields.item_id)?;
+        [
+            &fields.url,
+            &fields.name,
+            &fields.description,
+            &fields.price,
+            &fields.currency,
+            &fields.state,
+            &fields.category,
+            &fields.image_url,
+        ]
+        .into_iter()
+        .flatten()
+        .try_for_each(|pointer| check_pointer(pointer))?;
+        if let JsonApiPagination::Cursor {
+            next_cursor_pointer,
+        } = &config.pagination
+        {
+            check_pointer(next_cursor_pointer)?;
+        }
+        if (fields.name.is_some() || fields.description.is_some())
+            && scraper_config.language.is_none()
+        {
+            return Err(ScraperConfigError::Missing { field: "language" });
+        }
+        if let Some(checkpoint) = &scraper_config.checkpoint
+            && !matches!(
+                (&config.pagination, &checkpoint.next_page),
+                (JsonApiPagination::Page { .. }, PageToken::Number { .. })
+                    | (JsonApiPagination::Offset { .. }, PageToken::Offset { .. })
+                    | (JsonApiPagination::Cursor { .. }, PageToken::Cursor { .. })
+            )
+        {
+            return Err(ScraperConfigError::InvalidCheckpoint {
+                next_page: checkpoint.next_page.to_string(),
+            });
+        }
+        Url::parse(&config.request.url).map_err(|e| ScraperConfigError::InvalidUrl {
+            url: config.request.url.clone(),
+            reason: e.to_string(),
+        })?;
+        states.overrides(&config.states);
+
+        Ok(JsonApiScraper {
+            base_url: scraper_config.base_url,
+            config,
+            states,
+            currency: scraper_config.currency,
+            language: scraper_config.language,
+        })
+    }
+}
+
+fn variables(token: &PageToken) -> Vec<(&'static str, String)> {
+    let (page, offset, limit, cursor) = match token {
+        PageToken::Number { page } => (page.to_string(), "".into(), "".into(), "".into()),
+        PageToken::Offset { offset, limit } => {
+            ("".into(), offset.to_string(), limit.to_string(), "".into())
+        }
+        PageToken::Cursor { cursor } => (
+            "".into(),
+            "".into(),
+            "".into(),
+            cursor.clone().unwrap_or_default(),
+        ),
+        PageToken::NextLink { .. } => Default::default(),
+    };
+    vec![
+        ("page", page),
+        ("offset", offset),
+        ("limit", limit),
+        ("cursor", cursor),
+    ]
+}
+
+fn render(template: &str, variables: &[(&str, String)]) -> String {
+    variables
+        .iter()
+        .fold(template.to_string(), |rendered, (name, value)| {
+            rendered.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+fn render_json(template: &Value, variables: &[(&str, String)]) -> Value {
+    match template {
+        Value::String(string) => {
+            let numeric = variables.iter().find_map(|(name, value)| {
+                (*string == format!("{{{}}}", name))
+                    .then(|| value.parse::<i64>().ok())
+                    .flatten()
+            });
+            match numeric {
+                Some(number) => Value::from(number),
+                None => Value::String(render(string, variables)),
+            }
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| render_json(value, variables))
+                .collect(),
+        ),
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), render_json(value, variables)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Strings as they are, numbers and booleans as their JSON text. Nothing for other values.
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string.trim().to_string()).filter(|s| !s.is_empty()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(bool) => Some(bool.to_string()),
+        _ => None,
+    }
+}
+
+type ItemResults = Vec<Result<ItemData, ScrapeError>>;
+
+impl JsonApiScraper {
+    pub fn request_url(&self, token: &PageToken) -> Option<Url> {
+        let variables = variables(token);
+        let mut url = Url::parse(&render(&self.config.request.url, &variables)).ok()?;
+        let mut query = self
+            .config
+            .request
+            .query
+            .iter()
+            .map(|(name, value)| (name.clone(), render(value, &variables)))
+            .filter(|(_, value)| !value.is_empty())
+            .collect::<Vec<_>>();
+        if !query.is_empty() {
+            // sorted, so URLs are stable for logs and robots.txt
+            query.sort();
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        Some(url)
+    }
+
+    pub fn parse_response(
+        &self,
+        response: &Value,
+        url: &str,
+    ) -> Result<(ItemResults, Option<String>), ScrapeError> {
+        let items = response
+            .pointer(&self.config.items_pointer)
+            .and_then(Value::as_array)
+            .ok_or_else(|| ScrapeError::Parse {
+                url: url.to_string(),
+                reason: format!("no item array at '{}'", self.config.items_pointer),
+            })?
+            .iter()
+            .map(|item| self.parse_item(item, url))
+            .collect();
+        let next_cursor = match &self.config.pagination {
+            JsonApiPagination::Cursor {
+                next_cursor_pointer,
+            } => response.pointer(next_cursor_pointer).and_then(value_text),
+            _ => None,
+        };
+        Ok((items, next_cursor))
+    }
+
+    fn parse_item(&self, value: &Value, url: &str) -> Result<ItemData, ScrapeError> {
+        let fields = &self.config.fields;
+        let extract = |pointer: &Option<String>| {
+            pointer
+                .as_ref()
+                .and_then(|pointer| value.pointer(pointer))
+                .filter(|value| !value.is_null())
+        };
+        let text = |pointer: &Option<String>| extract(pointer).and_then(value_text);
+        let id = value
+            .pointer(&fields.item_id)
+            .and_then(value_text)
+            .ok_or_else(|| ScrapeError::InvalidItem {
+                item_id: url.to_string(),
+                reason: "missing id".to_string(),
+            })?;
+
+        let mut item = ItemData::new(format!("{}#{}", self.base_url, id))
+            .source_id(self.base_url.clone())
+            .to_owned();
+        if let Some(url) = text(&fields.url) {
+            item.url(url);
+        }
+        let state = text(&fields.state)
+            .and_then(|state| self.states.classify(&state))
+            .or_else(|| self.config.default_state.clone());
+        if let Some(state) = state {
+            item.state(state);
+        }
+        let currency = text(&fields.currency)
+            .and_then(|code| serde_json::from_value::<Currency>(Value::String(code)).ok())
+            .or_else(|| self.currency.clone());
+        let price = extract(&fields.price).map(|price| match (price, currency) {
+            (Value::Number(number), Some(currency)) => number
+                .as_f64()
+                .map(|amount| Price::new(currency, amount as f32))
+                .ok_or_else(|| format!("invalid amount {}", number)),
+            (Value::Number(number), None) => Err(format!("no currency for {}", number)),
+            (other, currency) => value_text(other)
+                .ok_or_else(|| format!("invalid price {}", other))
+                .and_then(|text| {
+                    parse_price(&text, currency.as_ref(), self.language.as_ref())
+                        .map_err(|e| e.to_string())
+                }),
+        });
+        if let Some(price) = price {
+            let price = price.map_err(|reason| ScrapeError::InvalidItem {
+                item_id: item.item_id.clone(),
+                reason,
+            })?;
+            item.price(price);
+        }
+        if let Some(language) = &self.language {
+            if let Some(name) = text(&fields.name) {
+                item.name.insert(language.clone(), name);
+            }
+            if let Some(description) = text(&fields.description) {
+                item.description.insert(language.clone(), description);
+            }
+        }
+        item.category = text(&fields.category);
+        item.image_url = text(&fields.image_url);
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl Scraper for JsonApiScraper {
+    fn first_page(&self) -> PageToken {
+        match &self.config.pagination {
+            JsonApiPagination::Page { first_page } => PageToken::Number { page: *first_page },
+            JsonApiPagination::Offset { limit } => PageToken::first_offset(*limit),
+            JsonApiPagination::Cursor { .. } => PageToken::first_cursor(),
+        }
+    }
+
+    fn page_url(&self, token: &PageToken) -> Option<String> {
+        self.request_url(token).map(|url| url.to_string())
+    }
+
+    async fn fetch_page(
+        &self,
+        token: &PageToken,
+        client: &ScrapeClient,
+    ) -> Result<Page, ScrapeError> {
+        let url = self.request_url(token).ok_or_else(|| ScrapeError::Parse {
+            url: self.config.request.url.clone(),
+            reason: format!("invalid URL for page {}", token),
+        })?;
+        let variables = variables(token);
+        let mut request = match self.config.request.method {
+            HttpMethod::Get => client.get(url.clone()),
+            HttpMethod::Post => client.post(url.clone()),
+        };
+        for (name, value) in &self.config.request.headers {
+            request = request.header(name, &render(value, &variables));
+        }
+        if let Some(body) = &self.config.request.body {
+            request = request.json(&render_json(body, &variables));
+        }
+
+        let response = request.send().await?.error_for_status()?;
+        let json = response.json::<Value>().await?;
+        let (results, next_cursor) = self.parse_response(&json, url.as_str())?;
+
+        // pages of only invalid items still count as non-empty
+        let next = match token {
+            PageToken::Number { page } if !results.is_empty() => {
+                Some(PageToken::Number { page: page + 1 })
+            }
+            PageToken::Offset { offset, limit } if results.len() as u64 >= *limit => {
+                Some(PageToken::Offset {
+                    offset: offset + limit,
+                    limit: *limit,
+                })
+            }
+            PageToken::Number { .. } | PageToken::Offset { .. } => None,
+            _ => next_cursor
+                .filter(|cursor| !cursor.is_empty())
+                .map(|cursor| PageToken::Cursor {
+                    cursor: Some(cursor),
+                }),
+        };
+        Ok(Page::from_results(results, next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json_api_scraper::{JsonApiScraper, JsonApiScraperConfig, render_json};
+    use crate::pagination::PageToken;
+    use crate::scraper::{ScrapeError, Scraper};
+    use crate::scraper_config::{ScraperConfig, ScraperConfigError};
+    use item_core::item_state::ItemState::{AVAILABLE, RESERVED};
+    use item_core::language::Language::EN;
+    use item_core::price::Currency::EUR;
+    use item_core::price::Price;
+    use serde_json::json;
+
+    fn make_scraper(pagination: serde_json::Value) -> JsonApiScraper {
+        let config: JsonApiScraperConfig = serde_json::from_value(json!({
+            "request": {
+                "url": "https://api.foo.bar/products",
+                "query": { "page": "{page}", "offset": "{offset}", "size": "{limit}", "after": "{cursor}" }
+            },
+            "pagination": pagination,
+            "itemsPointer": "/data/products",
+            "fields": {
+                "itemId": "/id",
+                "url": "/links/self",
+                "name": "/title",
+                "price": "/price/amount",
+                "currency": "/price/currency",
+                "state": "/availability"
+            },
+            "states": { "reserved": "RESERVED" },
+            "defaultState": "AVAILABLE"
+        }))
+        .unwrap();
+        ScraperConfig::new("https://foo.bar".to_string())
+            .language(EN)
+            .json_api_scraper(config)
+            .clone()
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn should_map_items_by_pointers() {
+        let scraper = make_scraper(json!({ "type": "cursor", "nextCursorPointer": "/data/next" }));
+        let response = json!({
+            "data": {
+                "products": [
+                    {
+                        "id": 42,
+                        "links": { "self": "https://foo.bar/p/42" },
+                        "title": "Helmet",
+                        "price": { "amount": 120.5, "currency": "EUR" },
+                        "availability": "reserved"
+                    },
+                    { "id": "43", "title": "Buckle", "price": { "amount": "85,00 €", "currency": "EUR" } },
+                    { "title": "Without id" },
+                    { "id": 44, "title": "Medal", "price": { "amount": "on request" } }
+                ],
+                "next": "c2"
+            }
+        });
+
+        let (mut results, next_cursor) = scraper
+            .parse_response(&response, "https://api.foo.bar")
+            .unwrap();
+        let errors = results.split_off(2);
+        let items = results.into_iter().flatten().collect::<Vec<_>>();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].item_id, "https://foo.bar#42");
+        assert_eq!(items[0].url, Some("https://foo.bar/p/42".to_string()));
+        assert_eq!(items[0].name.get(&EN), Some(&"Helmet".to_string()));
+        assert_eq!(items[0].price, Some(Price::new(EUR, 120.5)));
+        assert_eq!(items[0].state, Some(RESERVED));
+        assert_eq!(items[1].price, Some(Price::new(EUR, 85.0)));
+        assert_eq!(items[1].state, Some(AVAILABLE));
+        assert!(matches!(
+            &errors[0],
+            Err(ScrapeError::InvalidItem { item_id, reason })
+                if item_id == "https://api.foo.bar" && reason == "missing id"
+        ));
+        assert!(matches!(
+            &errors[1],
+            Err(ScrapeError::InvalidItem { item_id, .. }) if item_id == "https://foo.bar#44"
+        ));
+        assert_eq!(next_cursor, Some("c2".to_string()));
+    }
+
+    #[test]
+    fn should_fail_without_item_array() {
+        let scraper = make_scraper(json!({ "type": "page" }));
+
+        let actual = scraper.parse_response(&json!({ "error": "oops" }), "https://api.foo.bar");
+
+        assert!(matches!(actual, Err(ScrapeError::Parse { .. })));
+    }
+
+    #[test]
+    fn should_render_query_for_page_tokens() {
+        let scraper = make_scraper(json!({ "type": "offset", "limit": 50 }));
+
+        assert_eq!(scraper.first_page(), PageToken::first_offset(50));
+        assert_eq!(
+            scraper.page_url(&PageToken::Offset {
+                offset: 100,
+                limit: 50
+            }),
+            Some("https://api.foo.bar/products?offset=100&size=50".to_string())
+        );
+        assert_eq!(
+            scraper.page_url(&PageToken::first_cursor()),
+            Some("https://api.foo.bar/products".to_string())
+        );
+    }
+
+    #[test]
+    fn should_render_numeric_body_variables_as_numbers() {
+        let body = json!({ "page": "{page}", "filter": { "label": "page {page}" } });
+
+        let actual = render_json(
+            &body,
+            &[("page", "2".to_string()), ("cursor", String::new())],
+        );
+
+        assert_eq!(
+            actual,
+            json!({ "page": 2, "filter": { "label": "page 2" } })
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_pointers() {
+        let mut config = ScraperConfig::new("https://foo.bar".to_string());
+        config.json_api_scraper(
+            serde_json::from_value(json!({
+                "request": { "url": "https://api.foo.bar/products" },
+                "pagination": { "type": "page" },
+                "fields": { "itemId": "id" }
+            }))
+            .unwrap(),
+        );
+
+        let actual = JsonApiScraper::try_from(config);
+
+        assert!(matches!(
+            actual,
+            Err(ScraperConfigError::InvalidPointer { .. })
+        ));
+    }
+}
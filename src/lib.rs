@@ -1,39 +1,92 @@
+pub mod batcher;
+pub mod change_detection;
+pub mod checkpoint;
+pub mod circuit_breaker;
+#[cfg(feature = "cli")]
+pub mod cli;
 pub mod default_handler;
 pub mod hash_comparison;
+pub mod hash_store;
+pub mod json_api_scraper;
+pub mod pagination;
+pub mod price_parser;
+pub mod rate_limiter;
+pub mod removal;
+pub mod report;
+pub mod retry;
+pub mod robots;
+pub mod scrape_client;
 pub mod scraper;
 pub mod scraper_config;
+pub mod selector_scraper;
+pub mod sink;
+pub mod sqs_sink;
+pub mod state_classifier;
+pub mod structured_data;
+pub mod throttle;
 
-use crate::hash_comparison::drop_unchanged_diffs;
+use crate::change_detection::{DEFAULT_CHANGE_DETECTION_FIELDS, ItemDiff, detect_changes};
+use crate::checkpoint::Checkpoint;
+use crate::hash_store::{HashStore, HashStoreError};
+use crate::removal::{removal_ratio, removed_item_diffs};
+use crate::report::{PushFailure, PushResult, ScrapeReport, millis};
+use crate::scrape_client::ScrapeClient;
 use crate::scraper::Scraper;
 use crate::scraper_config::ScraperConfig;
-use aws_sdk_dynamodb::operation::query::QueryError;
-use aws_sdk_sqs::config::http::HttpResponse;
-use aws_sdk_sqs::error::SdkError;
-use aws_sdk_sqs::types::SendMessageBatchRequestEntry;
-use futures::StreamExt;
+use crate::sink::{InMemorySink, ItemSink};
+use futures::future::{join_all, pending, ready};
+use futures::{StreamExt, stream};
 pub use item_core;
 use item_core::item_data::ItemData;
-use item_read::item_hash::get_latest_item_event_hash_map_by_source_id;
 use lambda_runtime::Diagnostic;
+use std::collections::HashSet;
 use std::error::Error;
 use std::fmt::Display;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Instant;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time::sleep_until;
 use tracing::{error, info, warn};
-use uuid::Uuid;
 
 pub const MAX_SQS_BATCH_SIZE: usize = 10;
+pub const DEFAULT_ENRICH_CONCURRENCY: usize = 4;
 
 #[derive(Debug)]
 pub enum ScrapePushError {
-    QueryItemEventHashesError(SdkError<QueryError, HttpResponse>),
+    LoadHashesError(HashStoreError),
+    InvalidScraperConfig(String),
+    PushLossThresholdExceeded {
+        failures: Vec<PushFailure>,
+        attempted: usize,
+    },
+    CircuitBreakerTripped {
+        reason: String,
+        report: Box<ScrapeReport>,
+    },
 }
 
 impl Display for ScrapePushError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ScrapePushError::QueryItemEventHashesError(err) => {
-                write!(f, "QueryItemEventHashesError error: {}", err)
+            ScrapePushError::LoadHashesError(err) => {
+                write!(f, "LoadHashesError error: {}", err)
+            }
+            ScrapePushError::InvalidScraperConfig(reason) => {
+                write!(f, "InvalidScraperConfig error: {}", reason)
+            }
+            ScrapePushError::PushLossThresholdExceeded {
+                failures,
+                attempted,
+            } => write!(
+                f,
+                "PushLossThresholdExceeded error: {} of {} items couldn't be pushed: {:?}",
+                failures.len(),
+                attempted,
+                failures
+            ),
+            ScrapePushError::CircuitBreakerTripped { reason, .. } => {
+                write!(f, "CircuitBreakerTripped error: {}", reason)
             }
         }
     }
@@ -42,24 +95,39 @@ impl Display for ScrapePushError {
 impl Error for ScrapePushError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            ScrapePushError::QueryItemEventHashesError(err) => Some(err),
+            ScrapePushError::LoadHashesError(err) => Some(err),
+            ScrapePushError::InvalidScraperConfig(_) => None,
+            ScrapePushError::PushLossThresholdExceeded { .. } => None,
+            ScrapePushError::CircuitBreakerTripped { .. } => None,
         }
     }
 }
 
-impl From<SdkError<QueryError, HttpResponse>> for ScrapePushError {
-    fn from(err: SdkError<QueryError, HttpResponse>) -> Self {
-        ScrapePushError::QueryItemEventHashesError(err)
+impl From<HashStoreError> for ScrapePushError {
+    fn from(err: HashStoreError) -> Self {
+        ScrapePushError::LoadHashesError(err)
     }
 }
 
 impl Into<Diagnostic> for ScrapePushError {
     fn into(self) -> Diagnostic {
         match self {
-            ScrapePushError::QueryItemEventHashesError(err) => Diagnostic {
-                error_type: "QueryItemEventHashesError".to_string(),
+            ScrapePushError::LoadHashesError(err) => Diagnostic {
+                error_type: err.kind().to_string(),
+                error_message: err.to_string(),
+            },
+            ScrapePushError::InvalidScraperConfig(reason) => Diagnostic {
+                error_type: "InvalidScraperConfig".to_string(),
+                error_message: reason,
+            },
+            err @ ScrapePushError::PushLossThresholdExceeded { .. } => Diagnostic {
+                error_type: "PushLossThresholdExceeded".to_string(),
                 error_message: err.to_string(),
             },
+            ScrapePushError::CircuitBreakerTripped { reason, .. } => Diagnostic {
+                error_type: "CircuitBreakerTripped".to_string(),
+                error_message: reason,
+            },
         }
     }
 }
@@ -68,94 +136,830 @@ pub async fn scrape_and_push(
     scraper: &impl Scraper,
     scraper_config: &ScraperConfig,
     reqwest_client: &reqwest::Client,
-    sqs_client: &aws_sdk_sqs::Client,
-    dynamodb_client: &aws_sdk_dynamodb::Client,
-    item_write_lambda_q_url: &str,
-) -> Result<usize, ScrapePushError> {
-    let total_sent_count = Arc::new(Mutex::new(0usize));
-    let item_hashes_map =
-        get_latest_item_event_hash_map_by_source_id(&scraper_config.base_url, dynamodb_client)
-            .await?;
+    sink: &dyn ItemSink,
+    hash_store: &dyn HashStore,
+) -> Result<ScrapeReport, ScrapePushError> {
+    scrape_and_push_until(
+        scraper,
+        scraper_config,
+        reqwest_client,
+        sink,
+        hash_store,
+        None,
+    )
+    .await
+}
+
+/// Removed items are never detected for runs stopped at `deadline`.
+pub async fn scrape_and_push_until(
+    scraper: &impl Scraper,
+    scraper_config: &ScraperConfig,
+    reqwest_client: &reqwest::Client,
+    sink: &dyn ItemSink,
+    hash_store: &dyn HashStore,
+    deadline: Option<tokio::time::Instant>,
+) -> Result<ScrapeReport, ScrapePushError> {
+    let started = Instant::now();
+    let dry_run = scraper_config.dry_run.unwrap_or(false);
+    let dry_run_sink = InMemorySink::new();
+    let sink: &dyn ItemSink = if dry_run {
+        info!("Dry run, not pushing anything.");
+        &dry_run_sink
+    } else {
+        sink
+    };
+    let report = Arc::new(Mutex::new(ScrapeReport::default()));
+    let scraped_item_ids = Arc::new(Mutex::new(HashSet::new()));
+    let item_fingerprints_map = hash_store.load(&scraper_config.base_url).await?;
+    let change_detection_fields = scraper_config
+        .change_detection_fields
+        .clone()
+        .unwrap_or_else(|| DEFAULT_CHANGE_DETECTION_FIELDS.to_vec());
+    if !hash_store.has_field_fingerprints()
+        && let Some(field) = change_detection_fields
+            .iter()
+            .find(|field| !field.is_hashed_by_item_core())
+    {
+        return Err(ScrapePushError::InvalidScraperConfig(format!(
+            "changeDetectionFields contains {}, but the hash store only knows item hashes",
+            field
+        )));
+    }
+    if !hash_store.has_field_fingerprints()
+        && !hash_store.has_states()
+        && let [field] = change_detection_fields
+            .iter()
+            .filter(|field| field.is_hashed_by_item_core())
+            .collect::<Vec<_>>()[..]
+    {
+        return Err(ScrapePushError::InvalidScraperConfig(format!(
+            "changeDetectionFields contains only {} of state and price, but the hash store can't tell them apart",
+            field
+        )));
+    }
+    let enrich_permits = Semaphore::new(
+        scraper_config
+            .enrich_concurrency
+            .unwrap_or(DEFAULT_ENRICH_CONCURRENCY)
+            .max(1),
+    );
+    let scrape_client = ScrapeClient::new(reqwest_client, scraper_config);
+    report.lock().await.timings.load_hashes_millis = millis(started.elapsed());
 
+    let scrape_started = Instant::now();
+    let next_page = Arc::new(Mutex::new(Some(match &scraper_config.checkpoint {
+        Some(checkpoint) => checkpoint.next_page.clone(),
+        None => scraper.first_page(),
+    })));
+    let deadline_reached = AtomicBool::new(false);
+    let consecutive_page_failures = AtomicU32::new(0);
+    let item_errors_tripped = AtomicBool::new(false);
     scraper
-        .scrape(reqwest_client, scraper_config.sleep_between_pages_millis)
+        .scrape_pages(reqwest_client, scraper_config)
+        .take_until(async {
+            match deadline {
+                Some(deadline) => {
+                    sleep_until(deadline).await;
+                    info!("Deadline reached, not scraping any further pages.");
+                    deadline_reached.store(true, Ordering::SeqCst);
+                }
+                None => pending().await,
+            }
+        })
+        .then(|page_result| async {
+            let mut locked_report = report.lock().await;
+            locked_report.record_page(&page_result);
+            if let Some(circuit_breaker) = &scraper_config.circuit_breaker
+                && circuit_breaker.check_item_errors(&locked_report).is_some()
+            {
+                info!("Too many items failed, not scraping or pushing any further.");
+                item_errors_tripped.store(true, Ordering::SeqCst);
+            }
+            drop(locked_report);
+            match &page_result {
+                Ok(page) => {
+                    *next_page.lock().await = page.next.clone();
+                    consecutive_page_failures.store(0, Ordering::SeqCst);
+                }
+                Err(_) => {
+                    consecutive_page_failures.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+            page_result
+        })
+        .take_while(|_| ready(!item_errors_tripped.load(Ordering::SeqCst)))
+        .flat_map(|page_result| match page_result {
+            Ok(page) => {
+                for e in &page.errors {
+                    warn!(error = %e, "Scraping item failed.");
+                }
+                stream::iter(page.items)
+            }
+            Err(e) => {
+                warn!(error = %e, "Scraping page failed.");
+                stream::iter(vec![])
+            }
+        })
         .chunks(MAX_SQS_BATCH_SIZE)
-        .for_each_concurrent(5, |diff_results| async {
-            let mut diffs = diff_results
-                .into_iter()
-                .filter_map(|diff_result| match diff_result {
-                    Ok(diff) => Some(diff),
-                    Err(e) => {
-                        warn!(error = %e, "Scraping item failed.");
-                        None
-                    }
-                })
-                .collect::<Vec<_>>();
-
-            drop_unchanged_diffs(&mut diffs, &item_hashes_map);
+        .for_each_concurrent(5, |items| async {
+            if item_errors_tripped.load(Ordering::SeqCst) {
+                return;
+            }
+            scraped_item_ids
+                .lock()
+                .await
+                .extend(items.iter().map(|item| item.item_id.clone()));
+            let items_count = items.len();
+            let diffs = detect_changes(items, &item_fingerprints_map, &change_detection_fields);
+            report.lock().await.items_unchanged += items_count - diffs.len();
+            // recorded as scraped, so the next run compares against the listing page again
+            let scraped_items = diffs.iter().map(|diff| diff.item.clone()).collect();
+            let diffs =
+                enrich_diffs(scraper, diffs, &scrape_client, &enrich_permits, &report).await;
 
             if !diffs.is_empty() {
-                push_diffs(
-                    diffs,
-                    sqs_client,
-                    item_write_lambda_q_url,
-                    &total_sent_count,
-                )
-                .await;
+                let push_result =
+                    push_and_record(diffs, scraped_items, sink, hash_store, scraper_config).await;
+                report.lock().await.record_push(push_result);
             }
         })
         .await;
+    report.lock().await.timings.scrape_and_push_millis = millis(scrape_started.elapsed());
+    if let Err(e) = hash_store.flush().await {
+        warn!(error = %e, "Flushing recorded items failed.");
+    }
+    let stopped_before = if deadline_reached.load(Ordering::SeqCst) {
+        next_page.lock().await.clone()
+    } else {
+        None
+    };
+
+    if let Some(circuit_breaker) = &scraper_config.circuit_breaker {
+        let report = report.lock().await;
+        let complete = stopped_before.is_none() && scraper_config.max_pages.is_none();
+        let items_scraped = complete.then(|| {
+            let previously_scraped = scraper_config
+                .checkpoint
+                .as_ref()
+                .map_or(0, |checkpoint| checkpoint.items_scraped);
+            report.items_scraped + previously_scraped
+        });
+        let tripped = circuit_breaker.check(
+            &report,
+            consecutive_page_failures.load(Ordering::SeqCst),
+            items_scraped,
+        );
+        if let Some(reason) = tripped {
+            error!(reason = %reason, report = ?*report, "Circuit breaker tripped.");
+            let mut report = report.clone();
+            report.timings.total_millis = millis(started.elapsed());
+            return Err(ScrapePushError::CircuitBreakerTripped {
+                reason,
+                report: Box::new(report),
+            });
+        }
+    }
 
-    Ok(*total_sent_count.lock().await)
+    if let Some(removed_state) = &scraper_config.removed_item_state {
+        let removal_started = Instant::now();
+        if report.lock().await.has_scrape_errors() {
+            warn!("Scraping was incomplete, not detecting removed items.");
+        } else if report.lock().await.has_item_errors() {
+            warn!("Some items couldn't be scraped, not detecting removed items.");
+        } else if scraper_config.max_pages.is_some() {
+            warn!("Scraping was limited to maxPages, not detecting removed items.");
+        } else if scraper_config.checkpoint.is_some() || stopped_before.is_some() {
+            warn!("Scraping was split at a checkpoint, not detecting removed items.");
+        } else {
+            let removed_diffs = removed_item_diffs(
+                &scraper_config.base_url,
+                &item_fingerprints_map,
+                &*scraped_item_ids.lock().await,
+                removed_state,
+            );
+            info!(total = removed_diffs.len(), "Detected removed items.");
+            let ratio = removal_ratio(
+                &removed_diffs,
+                &item_fingerprints_map,
+                &*scraped_item_ids.lock().await,
+            );
+            match scraper_config.max_removal_ratio {
+                Some(max_removal_ratio) if ratio > max_removal_ratio => {
+                    error!(
+                        removalRatio = ratio,
+                        maxRemovalRatio = max_removal_ratio,
+                        total = removed_diffs.len(),
+                        "Too many items vanished at once, not pushing removals."
+                    );
+                    report.lock().await.removals_withheld = removed_diffs.len();
+                }
+                _ => {
+                    report.lock().await.items_removed = removed_diffs.len();
+                    for diffs in removed_diffs.chunks(MAX_SQS_BATCH_SIZE) {
+                        let items = diffs.iter().map(|diff| diff.item.clone()).collect();
+                        let push_result = push_and_record(
+                            diffs.to_vec(),
+                            items,
+                            sink,
+                            hash_store,
+                            scraper_config,
+                        )
+                        .await;
+                        report.lock().await.record_push(push_result);
+                    }
+                    if let Err(e) = hash_store.flush().await {
+                        warn!(error = %e, "Flushing recorded items failed.");
+                    }
+                }
+            }
+        }
+        report.lock().await.timings.removal_millis = millis(removal_started.elapsed());
+    }
+
+    let mut report = report.lock().await.clone();
+    report.timings.total_millis = millis(started.elapsed());
+
+    if let Some(next_page) = stopped_before {
+        let checkpoint = Checkpoint::next(scraper_config.checkpoint.as_ref(), next_page, &report);
+        info!(checkpoint = ?checkpoint, "Stopped before the deadline, returning a continuation.");
+        report.continuation = Some(scraper_config.clone().checkpoint(checkpoint).clone());
+    }
+
+    if dry_run {
+        report.would_push = dry_run_sink.diffs();
+        for diff in &report.would_push {
+            info!(
+                itemId = %diff.item.item_id,
+                changeKinds = ?diff.change_kinds(),
+                body = ?diff.item,
+                "Would push item."
+            );
+        }
+    }
+
+    if let Some(max_push_loss_ratio) = scraper_config.max_push_loss_ratio {
+        let loss_ratio = report.push_loss_ratio();
+        if loss_ratio > max_push_loss_ratio {
+            error!(
+                lossRatio = loss_ratio,
+                maxLossRatio = max_push_loss_ratio,
+                report = ?report,
+                "Too many items couldn't be pushed."
+            );
+            let attempted = report.items_pushed + report.push_failures.len();
+            return Err(ScrapePushError::PushLossThresholdExceeded {
+                failures: report.push_failures,
+                attempted,
+            });
+        }
+    }
+
+    Ok(report)
 }
 
-async fn push_diffs(
-    diffs: Vec<ItemData>,
-    sqs_client: &aws_sdk_sqs::Client,
-    item_write_lambda_q_url: &str,
-    total_sent_count: &Arc<Mutex<usize>>,
-) {
-    let msg_entries = diffs
+async fn enrich_diffs(
+    scraper: &impl Scraper,
+    diffs: Vec<ItemDiff>,
+    client: &ScrapeClient,
+    permits: &Semaphore,
+    report: &Mutex<ScrapeReport>,
+) -> Vec<ItemDiff> {
+    join_all(diffs.into_iter().map(|mut diff| async move {
+        let _permit = permits
+            .acquire()
+            .await
+            .expect("enrich semaphore is never closed");
+        if let Err(e) = scraper.enrich_item(&mut diff.item, client).await {
+            warn!(
+                error = %e,
+                itemId = %diff.item.item_id,
+                "Enriching item failed, pushing it as scraped."
+            );
+            report.lock().await.enrich_errors += 1;
+        }
+        diff
+    }))
+    .await
+}
+
+/// Records `items` as scraped, before enrichment, so enriched fields never count as changes.
+async fn push_and_record(
+    diffs: Vec<ItemDiff>,
+    items: Vec<ItemData>,
+    sink: &dyn ItemSink,
+    hash_store: &dyn HashStore,
+    scraper_config: &ScraperConfig,
+) -> PushResult {
+    let push_result = sink.push(diffs, scraper_config).await;
+    if scraper_config.dry_run.unwrap_or(false) {
+        return PushResult::default();
+    }
+
+    let pushed_items = items
         .into_iter()
-        .filter_map(|diff| match serde_json::to_string(&diff) {
-            Ok(body) => Some(
-                SendMessageBatchRequestEntry::builder()
-                    .message_body(body)
-                    .id(Uuid::new_v4().to_string())
-                    .build()
-                    .expect("shouldn't fail because 'id' and 'message_body' have been set"),
-            ),
-            Err(e) => {
-                error!(
-                    error = %e,
-                    body = ?diff,
-                    "Serializing ItemData failed.",
-                );
-                None
-            }
+        .filter(|item| {
+            !push_result
+                .failures
+                .iter()
+                .any(|failure| failure.item_id == item.item_id)
         })
-        .collect::<Vec<_>>();
+        .collect::<Vec<ItemData>>();
+    if let Err(e) = hash_store
+        .record(&scraper_config.base_url, &pushed_items)
+        .await
+    {
+        warn!(error = %e, "Recording pushed items failed.");
+    }
+    push_result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::change_detection::{ItemField, ItemFingerprint};
+    use crate::checkpoint::Checkpoint;
+    use crate::circuit_breaker::CircuitBreaker;
+    use crate::hash_store::{HashStore, HashStoreError, InMemoryHashStore};
+    use crate::pagination::PageToken;
+    use crate::scrape_client::ScrapeClient;
+    use crate::scraper::{ScrapeError, Scraper};
+    use crate::scraper_config::ScraperConfig;
+    use crate::sink::InMemorySink;
+    use crate::{ScrapePushError, scrape_and_push, scrape_and_push_until};
+    use async_trait::async_trait;
+    use item_core::item_data::ItemData;
+    use item_core::item_state::ItemState;
+    use reqwest::Client;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use test_api::generator::Generator;
+    use tokio::time::{Instant, sleep};
+
+    struct TestScraper {
+        items: Vec<ItemData>,
+        enriched: AtomicUsize,
+    }
+
+    impl TestScraper {
+        fn new(items: Vec<ItemData>) -> Self {
+            TestScraper {
+                items,
+                enriched: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Scraper for TestScraper {
+        async fn scrape_page(
+            &self,
+            page_num: i16,
+            _: &ScrapeClient,
+        ) -> Result<Vec<ItemData>, ScrapeError> {
+            match page_num {
+                1 => Ok(self.items.clone()),
+                _ => Ok(vec![]),
+            }
+        }
+
+        async fn enrich_item(
+            &self,
+            item: &mut ItemData,
+            _: &ScrapeClient,
+        ) -> Result<(), ScrapeError> {
+            self.enriched.fetch_add(1, Ordering::SeqCst);
+            if item.item_id.ends_with("fail") {
+                return Err(ScrapeError::Parse {
+                    url: item.item_id.clone(),
+                    reason: "no detail page".to_string(),
+                });
+            }
+            item.image_url = Some(format!("{}/large.jpg", item.item_id));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn should_return_diffs_instead_of_pushing_for_dry_run() {
+        let items = ItemData::generate_many(3);
+        let scraper = TestScraper::new(items.clone());
+        let hash_store = InMemoryHashStore::with_items("https://foo.bar", &items[..1]);
+        let sink = InMemorySink::new();
+
+        let report = scrape_and_push(
+            &scraper,
+            ScraperConfig::new("https://foo.bar".to_string()).dry_run(true),
+            &Client::new(),
+            &sink,
+            &hash_store,
+        )
+        .await
+        .unwrap();
 
-    let batch_output_res = sqs_client
-        .send_message_batch()
-        .queue_url(item_write_lambda_q_url)
-        .set_entries(Some(msg_entries))
-        .send()
+        assert!(sink.diffs().is_empty());
+        assert_eq!(report.items_unchanged, 1);
+        assert_eq!(report.items_pushed, 0);
+        assert_eq!(report.would_push.len(), 2);
+        assert!(report.would_push.iter().all(|diff| diff.created));
+        assert_eq!(hash_store.load("https://foo.bar").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_push_and_record_diffs() {
+        let items = ItemData::generate_many(3);
+        let scraper = TestScraper::new(items.clone());
+        let hash_store = InMemoryHashStore::new();
+        let sink = InMemorySink::new();
+
+        let report = scrape_and_push(
+            &scraper,
+            &ScraperConfig::new("https://foo.bar".to_string()),
+            &Client::new(),
+            &sink,
+            &hash_store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.items_pushed, 3);
+        assert!(report.would_push.is_empty());
+        assert_eq!(sink.diffs().len(), 3);
+        assert_eq!(hash_store.load("https://foo.bar").await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn should_only_enrich_new_and_changed_items() {
+        let mut items = ItemData::generate_many(4);
+        items[3].item_id = format!("{}-fail", items[3].item_id);
+        let scraper = TestScraper::new(items.clone());
+        let hash_store = InMemoryHashStore::with_items("https://foo.bar", &items[..1]);
+        let sink = InMemorySink::new();
+
+        let report = scrape_and_push(
+            &scraper,
+            ScraperConfig::new("https://foo.bar".to_string()).enrich_concurrency(2),
+            &Client::new(),
+            &sink,
+            &hash_store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(scraper.enriched.load(Ordering::SeqCst), 3);
+        assert_eq!(report.enrich_errors, 1);
+        assert_eq!(report.items_pushed, 3);
+        for diff in sink.diffs() {
+            let expected = if diff.item.item_id.ends_with("fail") {
+                items[3].image_url.clone()
+            } else {
+                Some(format!("{}/large.jpg", diff.item.item_id))
+            };
+            assert_eq!(diff.item.image_url, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_not_enrich_again_on_next_run() {
+        let items = ItemData::generate_many(3);
+        let hash_store = InMemoryHashStore::new();
+        let scraper_config = ScraperConfig::new("https://foo.bar".to_string())
+            .change_detection_fields(vec![ItemField::State, ItemField::ImageUrl])
+            .clone();
+
+        let first_scraper = TestScraper::new(items.clone());
+        scrape_and_push(
+            &first_scraper,
+            &scraper_config,
+            &Client::new(),
+            &InMemorySink::new(),
+            &hash_store,
+        )
+        .await
+        .unwrap();
+        let second_scraper = TestScraper::new(items);
+        let report = scrape_and_push(
+            &second_scraper,
+            &scraper_config,
+            &Client::new(),
+            &InMemorySink::new(),
+            &hash_store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(first_scraper.enriched.load(Ordering::SeqCst), 3);
+        assert_eq!(second_scraper.enriched.load(Ordering::SeqCst), 0);
+        assert_eq!(report.items_unchanged, 3);
+        assert_eq!(report.items_pushed, 0);
+    }
+
+    /// Knows only item-core's hashes, like item events in DynamoDB.
+    struct HashOnlyStore {}
+
+    #[async_trait]
+    impl HashStore for HashOnlyStore {
+        async fn load(&self, _: &str) -> Result<HashMap<String, ItemFingerprint>, HashStoreError> {
+            Ok(HashMap::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn should_reject_fields_the_hash_store_cannot_compare() {
+        let result = scrape_and_push(
+            &TestScraper::new(ItemData::generate_many(1)),
+            ScraperConfig::new("https://foo.bar".to_string())
+                .change_detection_fields(vec![ItemField::State, ItemField::Name]),
+            &Client::new(),
+            &InMemorySink::new(),
+            &HashOnlyStore {},
+        )
         .await;
 
-    match batch_output_res {
-        Ok(batch_output) => {
-            let failures = batch_output.failed;
-            let successes = batch_output.successful.len();
-            info!(
-                successful = successes,
-                failed = failures.len(),
-                failures = ?failures,
-                "Successfully sent batch."
-            );
+        assert!(matches!(
+            result,
+            Err(ScrapePushError::InvalidScraperConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_reject_state_without_price_if_the_hash_store_cannot_tell_them_apart() {
+        let result = scrape_and_push(
+            &TestScraper::new(ItemData::generate_many(1)),
+            ScraperConfig::new("https://foo.bar".to_string())
+                .change_detection_fields(vec![ItemField::State]),
+            &Client::new(),
+            &InMemorySink::new(),
+            &HashOnlyStore {},
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(ScrapePushError::InvalidScraperConfig(_))
+        ));
+    }
+
+    struct HangingScraper {
+        items: Vec<ItemData>,
+    }
+
+    #[async_trait]
+    impl Scraper for HangingScraper {
+        async fn scrape_page(
+            &self,
+            page_num: i16,
+            _: &ScrapeClient,
+        ) -> Result<Vec<ItemData>, ScrapeError> {
+            if page_num > 1 {
+                sleep(Duration::from_secs(60)).await;
+            }
+            Ok(self.items.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn should_return_continuation_when_deadline_is_reached() {
+        let scraper = HangingScraper {
+            items: ItemData::generate_many(2),
+        };
+        let sink = InMemorySink::new();
+        let scraper_config = ScraperConfig::new("https://foo.bar".to_string())
+            .removed_item_state(ItemState::REMOVED)
+            .clone();
+
+        let report = scrape_and_push_until(
+            &scraper,
+            &scraper_config,
+            &Client::new(),
+            &sink,
+            &InMemoryHashStore::new(),
+            Some(Instant::now() + Duration::from_millis(200)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.pages_scraped, 1);
+        assert_eq!(report.items_pushed, 2);
+        assert_eq!(report.items_removed, 0);
+        assert_eq!(sink.diffs().len(), 2);
+        assert_eq!(
+            report.continuation.and_then(|config| config.checkpoint),
+            Some(Checkpoint {
+                next_page: PageToken::Number { page: 2 },
+                invocations: 1,
+                pages_scraped: 1,
+                items_scraped: 2,
+                items_pushed: 2,
+            })
+        );
+    }
+
+    struct PartlyBrokenScraper {
+        items: Vec<ItemData>,
+    }
+
+    #[async_trait]
+    impl Scraper for PartlyBrokenScraper {
+        async fn scrape_page_results(
+            &self,
+            page_num: i16,
+            _: &ScrapeClient,
+        ) -> Result<Vec<Result<ItemData, ScrapeError>>, ScrapeError> {
+            if page_num > 1 {
+                return Ok(vec![]);
+            }
+            let mut results = self.items.iter().cloned().map(Ok).collect::<Vec<_>>();
+            results.push(Err(ScrapeError::InvalidItem {
+                item_id: "https://foo.bar#broken".to_string(),
+                reason: "missing price".to_string(),
+            }));
+            Ok(results)
+        }
+    }
+
+    #[tokio::test]
+    async fn should_push_good_items_and_report_broken_ones() {
+        let items = ItemData::generate_many(3);
+        let scraper = PartlyBrokenScraper {
+            items: items[..2].to_vec(),
+        };
+        // the third item might be the broken one, so it mustn't be considered removed
+        let hash_store = InMemoryHashStore::with_items("https://foo.bar", &items[2..]);
+        let sink = InMemorySink::new();
+
+        let report = scrape_and_push(
+            &scraper,
+            ScraperConfig::new("https://foo.bar".to_string())
+                .removed_item_state(ItemState::REMOVED),
+            &Client::new(),
+            &sink,
+            &hash_store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.items_pushed, 2);
+        assert_eq!(report.item_errors.get("InvalidItem"), Some(&1));
+        assert!(!report.has_scrape_errors());
+        assert_eq!(report.items_removed, 0);
+        assert_eq!(sink.diffs().len(), 2);
+    }
+
+    struct RedesignedScraper {}
+
+    #[async_trait]
+    impl Scraper for RedesignedScraper {
+        async fn scrape_page(
+            &self,
+            page_num: i16,
+            _: &ScrapeClient,
+        ) -> Result<Vec<ItemData>, ScrapeError> {
+            match page_num {
+                1 => Ok(ItemData::generate_many(2)),
+                _ => Err(ScrapeError::Parse {
+                    url: format!("https://foo.bar?page={}", page_num),
+                    reason: "no items found".to_string(),
+                }),
+            }
+        }
+    }
 
-            *total_sent_count.lock().await += successes;
+    struct DegradingScraper {
+        items: Vec<ItemData>,
+    }
+
+    #[async_trait]
+    impl Scraper for DegradingScraper {
+        async fn scrape_page_results(
+            &self,
+            page_num: i16,
+            _: &ScrapeClient,
+        ) -> Result<Vec<Result<ItemData, ScrapeError>>, ScrapeError> {
+            Ok(match page_num {
+                1 => vec![Ok(self.items[0].clone())],
+                2 => (0..3)
+                    .map(|i| {
+                        Err(ScrapeError::InvalidItem {
+                            item_id: format!("https://foo.bar#broken{}", i),
+                            reason: "missing price".to_string(),
+                        })
+                    })
+                    .collect(),
+                3 => self.items[1..].iter().cloned().map(Ok).collect(),
+                _ => vec![],
+            })
         }
-        Err(e) => warn!(error = %e, "Failed message batch."),
+    }
+
+    #[tokio::test]
+    async fn should_stop_pushing_once_item_error_ratio_is_exceeded() {
+        let items = ItemData::generate_many(3);
+        let sink = InMemorySink::new();
+        let scraper_config = ScraperConfig::new("https://foo.bar".to_string())
+            .circuit_breaker(CircuitBreaker::new().max_item_error_ratio(0.5).clone())
+            .clone();
+
+        let actual = scrape_and_push(
+            &DegradingScraper {
+                items: items.clone(),
+            },
+            &scraper_config,
+            &Client::new(),
+            &sink,
+            &InMemoryHashStore::new(),
+        )
+        .await;
+
+        match actual {
+            Err(ScrapePushError::CircuitBreakerTripped { report, .. }) => {
+                assert_eq!(report.pages_scraped, 2);
+                assert_eq!(report.item_errors.get("InvalidItem"), Some(&3));
+            }
+            other => panic!("expected the circuit breaker to trip, got {:?}", other),
+        }
+        assert!(
+            sink.diffs()
+                .iter()
+                .all(|diff| diff.item.item_id == items[0].item_id)
+        );
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_circuit_breaker_trips() {
+        let hash_store = InMemoryHashStore::new();
+        let scraper_config = ScraperConfig::new("https://foo.bar".to_string())
+            .circuit_breaker(
+                CircuitBreaker::new()
+                    .max_consecutive_page_failures(3)
+                    .clone(),
+            )
+            .clone();
+
+        let actual = scrape_and_push(
+            &RedesignedScraper {},
+            &scraper_config,
+            &Client::new(),
+            &InMemorySink::new(),
+            &hash_store,
+        )
+        .await;
+
+        match actual {
+            Err(ScrapePushError::CircuitBreakerTripped { report, .. }) => {
+                assert_eq!(report.pages_scraped, 1);
+                assert_eq!(report.scrape_errors.get("Parse"), Some(&3));
+                assert_eq!(report.items_pushed, 2);
+            }
+            other => panic!("expected the circuit breaker to trip, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_too_few_items_were_scraped() {
+        let scraper = TestScraper::new(ItemData::generate_many(3));
+        let scraper_config = ScraperConfig::new("https://foo.bar".to_string())
+            .circuit_breaker(CircuitBreaker::new().min_items(10).clone())
+            .clone();
+
+        let actual = scrape_and_push(
+            &scraper,
+            &scraper_config,
+            &Client::new(),
+            &InMemorySink::new(),
+            &InMemoryHashStore::new(),
+        )
+        .await;
+
+        assert!(matches!(
+            actual,
+            Err(ScrapePushError::CircuitBreakerTripped { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_withhold_mass_removal() {
+        let mut items = ItemData::generate_many(4);
+        for item in &mut items {
+            item.state(ItemState::AVAILABLE);
+        }
+        let scraper = TestScraper::new(items[..1].to_vec());
+        let hash_store = InMemoryHashStore::with_items("https://foo.bar", &items);
+        let sink = InMemorySink::new();
+
+        let report = scrape_and_push(
+            &scraper,
+            ScraperConfig::new("https://foo.bar".to_string())
+                .removed_item_state(ItemState::REMOVED)
+                .max_removal_ratio(0.5),
+            &Client::new(),
+            &sink,
+            &hash_store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.items_removed, 0);
+        assert_eq!(report.removals_withheld, 3);
+        assert!(sink.diffs().is_empty());
     }
 }
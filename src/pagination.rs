@@ -0,0 +1,220 @@
+use crate::scraper::ScrapeError;
+use item_core::item_data::ItemData;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PageToken {
+    Number {
+        page: i16,
+    },
+    Offset {
+        offset: u64,
+        limit: u64,
+    },
+    Cursor {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        cursor: Option<String>,
+    },
+    NextLink {
+        url: String,
+    },
+}
+
+impl PageToken {
+    pub fn first_number() -> Self {
+        PageToken::Number { page: 1 }
+    }
+
+    pub fn first_offset(limit: u64) -> Self {
+        PageToken::Offset { offset: 0, limit }
+    }
+
+    pub fn first_cursor() -> Self {
+        PageToken::Cursor { cursor: None }
+    }
+
+    pub fn first_link(url: String) -> Self {
+        PageToken::NextLink { url }
+    }
+
+    /// Only numbered and offset pages can be skipped without fetching them.
+    pub fn skipped(&self) -> Option<PageToken> {
+        match self {
+            PageToken::Number { page } => Some(PageToken::Number { page: page + 1 }),
+            PageToken::Offset { offset, limit } => Some(PageToken::Offset {
+                offset: offset + limit,
+                limit: *limit,
+            }),
+            PageToken::Cursor { .. } | PageToken::NextLink { .. } => None,
+        }
+    }
+}
+
+impl Display for PageToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PageToken::Number { page } => write!(f, "{}", page),
+            PageToken::Offset { offset, limit } => write!(f, "offset={},limit={}", offset, limit),
+            PageToken::Cursor {
+                cursor: Some(cursor),
+            } => write!(f, "cursor={}", cursor),
+            PageToken::Cursor { cursor: None } => write!(f, "cursor=<initial>"),
+            PageToken::NextLink { url } => write!(f, "{}", url),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Page {
+    pub items: Vec<ItemData>,
+    pub errors: Vec<ScrapeError>,
+    pub next: Option<PageToken>,
+}
+
+impl Page {
+    pub fn new(items: Vec<ItemData>, next: Option<PageToken>) -> Self {
+        Page {
+            items,
+            errors: vec![],
+            next,
+        }
+    }
+
+    pub fn last(items: Vec<ItemData>) -> Self {
+        Page::new(items, None)
+    }
+
+    pub fn from_results(
+        results: Vec<Result<ItemData, ScrapeError>>,
+        next: Option<PageToken>,
+    ) -> Self {
+        let (items, errors): (Vec<_>, Vec<_>) = results.into_iter().partition(Result::is_ok);
+        Page {
+            items: items.into_iter().flatten().collect(),
+            errors: errors.into_iter().filter_map(Result::err).collect(),
+            next,
+        }
+    }
+
+    pub fn with_errors(mut self, errors: Vec<ScrapeError>) -> Self {
+        self.errors.extend(errors);
+        self
+    }
+
+    pub fn numbered(items: Vec<ItemData>, page: i16) -> Self {
+        let next = if items.is_empty() {
+            None
+        } else {
+            Some(PageToken::Number { page: page + 1 })
+        };
+        Page::new(items, next)
+    }
+
+    pub fn offset(items: Vec<ItemData>, offset: u64, limit: u64) -> Self {
+        let next = if (items.len() as u64) < limit || items.is_empty() {
+            None
+        } else {
+            Some(PageToken::Offset {
+                offset: offset + limit,
+                limit,
+            })
+        };
+        Page::new(items, next)
+    }
+
+    pub fn cursor(items: Vec<ItemData>, next_cursor: Option<String>) -> Self {
+        let next = next_cursor
+            .filter(|cursor| !cursor.is_empty())
+            .map(|cursor| PageToken::Cursor {
+                cursor: Some(cursor),
+            });
+        Page::new(items, next)
+    }
+
+    pub fn linked(items: Vec<ItemData>, next_url: Option<String>) -> Self {
+        let next = next_url
+            .filter(|url| !url.is_empty())
+            .map(|url| PageToken::NextLink { url });
+        Page::new(items, next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pagination::{Page, PageToken};
+    use crate::scraper::ScrapeError;
+    use item_core::item_data::ItemData;
+    use test_api::generator::Generator;
+
+    #[test]
+    fn should_continue_numbered_pages_until_empty() {
+        let page = Page::numbered(ItemData::generate_many(3), 4);
+        assert_eq!(page.next, Some(PageToken::Number { page: 5 }));
+
+        let page = Page::numbered(vec![], 5);
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn should_stop_offset_pages_on_short_page() {
+        let page = Page::offset(ItemData::generate_many(10), 20, 10);
+        assert_eq!(
+            page.next,
+            Some(PageToken::Offset {
+                offset: 30,
+                limit: 10
+            })
+        );
+
+        let page = Page::offset(ItemData::generate_many(7), 30, 10);
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn should_stop_cursor_pages_without_cursor() {
+        let page = Page::cursor(ItemData::generate_many(2), Some("abc".to_string()));
+        assert_eq!(
+            page.next,
+            Some(PageToken::Cursor {
+                cursor: Some("abc".to_string())
+            })
+        );
+
+        let page = Page::cursor(ItemData::generate_many(2), Some("".to_string()));
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn should_split_item_results() {
+        let items = ItemData::generate_many(2);
+        let results = vec![
+            Ok(items[0].clone()),
+            Err(ScrapeError::InvalidItem {
+                item_id: "https://foo.bar#2".to_string(),
+                reason: "missing price".to_string(),
+            }),
+            Ok(items[1].clone()),
+        ];
+
+        let page = Page::from_results(results, None);
+
+        assert_eq!(page.items, items);
+        assert_eq!(page.errors.len(), 1);
+        assert_eq!(page.errors[0].kind(), "InvalidItem");
+    }
+
+    #[test]
+    fn should_roundtrip_page_token_json() {
+        let token = PageToken::NextLink {
+            url: "https://foo.bar?page=2".to_string(),
+        };
+        let json = serde_json::to_string(&token).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"nextLink","url":"https://foo.bar?page=2"}"#
+        );
+        assert_eq!(serde_json::from_str::<PageToken>(&json).unwrap(), token);
+    }
+}
@@ -0,0 +1,416 @@
+//! Parses price texts of shop pages like `"1.234,56 €"`, `"€ 1,234.56"`, `"CHF 1'200.–"`
+//! or `"ab 20 EUR"`.
+
+use crate::scraper_config::ScraperConfig;
+use item_core::language::Language;
+use item_core::price::{Currency, Price};
+use std::error::Error;
+use std::fmt::Display;
+
+#[derive(Debug, PartialEq)]
+pub enum PriceParseError {
+    NoAmount {
+        text: String,
+    },
+    /// The text could be read in more than one way, e.g. `"1.234"` without a language hint
+    /// or `"20 - 30 €"`.
+    Ambiguous {
+        text: String,
+        reason: &'static str,
+    },
+    InvalidAmount {
+        text: String,
+    },
+    UnknownCurrency {
+        text: String,
+        currency: String,
+    },
+    MissingCurrency {
+        text: String,
+    },
+}
+
+impl Display for PriceParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PriceParseError::NoAmount { text } => write!(f, "No amount in '{}'", text),
+            PriceParseError::Ambiguous { text, reason } => {
+                write!(f, "Ambiguous price '{}': {}", text, reason)
+            }
+            PriceParseError::InvalidAmount { text } => write!(f, "Invalid amount in '{}'", text),
+            PriceParseError::UnknownCurrency { text, currency } => {
+                write!(f, "Unknown currency '{}' in '{}'", currency, text)
+            }
+            PriceParseError::MissingCurrency { text } => write!(f, "No currency for '{}'", text),
+        }
+    }
+}
+
+impl Error for PriceParseError {}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum NumberFormat {
+    DecimalPoint,
+    DecimalComma,
+}
+
+impl NumberFormat {
+    pub fn for_language(language: &Language) -> Self {
+        match language {
+            Language::EN => NumberFormat::DecimalPoint,
+            _ => NumberFormat::DecimalComma,
+        }
+    }
+
+    /// The format of the currency's home market, if it clearly has one.
+    pub fn for_currency(currency: &Currency) -> Option<Self> {
+        match currency {
+            Currency::USD | Currency::GBP | Currency::CHF | Currency::CAD | Currency::AUD => {
+                Some(NumberFormat::DecimalPoint)
+            }
+            _ => None,
+        }
+    }
+
+    fn decimal_separator(&self) -> char {
+        match self {
+            NumberFormat::DecimalPoint => '.',
+            NumberFormat::DecimalComma => ',',
+        }
+    }
+}
+
+const CURRENCY_SYMBOLS: [(&str, &str); 6] = [
+    ("€", "EUR"),
+    ("$", "USD"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("zł", "PLN"),
+    ("Kč", "CZK"),
+];
+
+const CURRENCY_CODES: [&str; 14] = [
+    "EUR", "USD", "GBP", "CHF", "JPY", "CNY", "SEK", "NOK", "DKK", "PLN", "CZK", "HUF", "CAD",
+    "AUD",
+];
+
+/// Separators grouping thousands only, never marking decimals.
+const GROUPING_ONLY: [char; 4] = ['\'', '’', '\u{a0}', '\u{202f}'];
+
+fn detect_currency_code(text: &str) -> Result<Option<&'static str>, PriceParseError> {
+    let mut codes = CURRENCY_SYMBOLS
+        .iter()
+        .filter(|(symbol, _)| text.contains(symbol))
+        .map(|(_, code)| *code)
+        .chain(
+            text.split(|c: char| !c.is_ascii_alphabetic())
+                .filter_map(|word| CURRENCY_CODES.iter().find(|code| **code == word).copied()),
+        )
+        .collect::<Vec<_>>();
+    codes.sort();
+    codes.dedup();
+    match codes.as_slice() {
+        [] => Ok(None),
+        [code] => Ok(Some(*code)),
+        _ => Err(PriceParseError::Ambiguous {
+            text: text.to_string(),
+            reason: "more than one currency",
+        }),
+    }
+}
+
+/// Runs of digits and separators. Separators only continue a run if a digit follows,
+/// so the `.–` of `"1'200.–"` ends it.
+fn number_runs(text: &str) -> Vec<String> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let is_separator = |c: char| c == '.' || c == ',' || c == ' ' || GROUPING_ONLY.contains(&c);
+    let mut runs = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let mut run = String::new();
+        while i < chars.len() {
+            let c = chars[i];
+            let continues = c.is_ascii_digit()
+                || (is_separator(c) && chars.get(i + 1).is_some_and(char::is_ascii_digit));
+            if !continues {
+                break;
+            }
+            run.push(c);
+            i += 1;
+        }
+        runs.push(run);
+    }
+    runs
+}
+
+/// Whether the spaces and other grouping-only separators of `run` group thousands,
+/// unlike e.g. the space between size and price in `"Gr. 5 10 €"`.
+fn valid_space_grouping(run: &str) -> bool {
+    let mut groups = run.split(|c: char| c == ' ' || GROUPING_ONLY.contains(&c));
+    let first_ok = groups.next().is_some_and(|first| {
+        (1..=3).contains(&first.len()) && first.chars().all(|c| c.is_ascii_digit())
+    });
+    first_ok
+        && groups.all(|group| {
+            let integer = group.find(['.', ',']).map_or(group, |at| &group[..at]);
+            integer.len() == 3
+        })
+}
+
+fn valid_grouping(number: &str, separator: char) -> bool {
+    let mut groups = number.split(separator);
+    let first_ok = groups
+        .next()
+        .is_some_and(|first| (1..=3).contains(&first.len()));
+    first_ok && groups.all(|group| group.len() == 3)
+}
+
+/// Without `format`, `"1.234"` and `"1,234"` are ambiguous.
+pub fn parse_amount(text: &str, format: Option<NumberFormat>) -> Result<f32, PriceParseError> {
+    let runs = number_runs(text);
+    let run = match runs.as_slice() {
+        [] => {
+            return Err(PriceParseError::NoAmount {
+                text: text.to_string(),
+            });
+        }
+        [run] => run,
+        _ => {
+            return Err(PriceParseError::Ambiguous {
+                text: text.to_string(),
+                reason: "more than one number",
+            });
+        }
+    };
+    let invalid = || PriceParseError::InvalidAmount {
+        text: text.to_string(),
+    };
+    if run.contains(|c: char| c == ' ' || GROUPING_ONLY.contains(&c)) && !valid_space_grouping(run)
+    {
+        return Err(PriceParseError::Ambiguous {
+            text: text.to_string(),
+            reason: "spaces don't group thousands",
+        });
+    }
+
+    let number = run
+        .chars()
+        .filter(|c| *c != ' ' && !GROUPING_ONLY.contains(c))
+        .collect::<String>();
+    let last_dot = number.rfind('.');
+    let last_comma = number.rfind(',');
+    let (integer, decimals) = match (last_dot, last_comma) {
+        (None, None) => (number.clone(), String::new()),
+        (Some(dot), Some(comma)) => {
+            let (decimal, grouping, at) = if dot > comma {
+                ('.', ',', dot)
+            } else {
+                (',', '.', comma)
+            };
+            let integer = &number[..at];
+            if integer.contains(decimal) || !valid_grouping(integer, grouping) {
+                return Err(invalid());
+            }
+            (integer.replace(grouping, ""), number[at + 1..].to_string())
+        }
+        (Some(at), None) | (None, Some(at)) => {
+            let separator = number[at..].chars().next().unwrap_or('.');
+            let occurrences = number.matches(separator).count();
+            let digits_after = number.len() - at - 1;
+            let is_decimal = if occurrences > 1 {
+                false
+            } else if digits_after != 3 || number.starts_with('0') {
+                true
+            } else {
+                match format {
+                    Some(format) => format.decimal_separator() == separator,
+                    None => {
+                        return Err(PriceParseError::Ambiguous {
+                            text: text.to_string(),
+                            reason: "separator could group thousands or mark decimals",
+                        });
+                    }
+                }
+            };
+            if is_decimal {
+                (number[..at].to_string(), number[at + 1..].to_string())
+            } else if valid_grouping(&number, separator) {
+                (number.replace(separator, ""), String::new())
+            } else {
+                return Err(invalid());
+            }
+        }
+    };
+
+    format!(
+        "{}.{}",
+        integer,
+        if decimals.is_empty() { "0" } else { &decimals }
+    )
+    .parse::<f32>()
+    .map_err(|_| invalid())
+}
+
+/// A currency in the text wins over `currency_hint`. The number format is taken from
+/// `language_hint`, else from the currency.
+pub fn parse_price(
+    text: &str,
+    currency_hint: Option<&Currency>,
+    language_hint: Option<&Language>,
+) -> Result<Price, PriceParseError> {
+    let currency =
+        match detect_currency_code(text)? {
+            Some(code) => serde_json::from_value::<Currency>(serde_json::Value::from(code))
+                .map_err(|_| PriceParseError::UnknownCurrency {
+                    text: text.to_string(),
+                    currency: code.to_string(),
+                })?,
+            None => currency_hint
+                .cloned()
+                .ok_or_else(|| PriceParseError::MissingCurrency {
+                    text: text.to_string(),
+                })?,
+        };
+    let format = language_hint
+        .map(NumberFormat::for_language)
+        .or_else(|| NumberFormat::for_currency(&currency));
+
+    Ok(Price::new(currency, parse_amount(text, format)?))
+}
+
+pub fn parse_price_for(
+    text: &str,
+    scraper_config: &ScraperConfig,
+) -> Result<Price, PriceParseError> {
+    parse_price(
+        text,
+        scraper_config.currency.as_ref(),
+        scraper_config.language.as_ref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::price_parser::{NumberFormat, PriceParseError, parse_amount, parse_price};
+    use item_core::language::Language;
+    use item_core::language::Language::{DE, EN};
+    use item_core::price::Currency::{EUR, USD};
+    use item_core::price::{Currency, Price};
+
+    #[test]
+    fn should_parse_amounts() {
+        use NumberFormat::{DecimalComma, DecimalPoint};
+        let cases: Vec<(&str, Option<NumberFormat>, f32)> = vec![
+            ("1.234,56 €", None, 1234.56),
+            ("€ 1,234.56", None, 1234.56),
+            ("CHF 1'200.–", None, 1200.0),
+            ("CHF 1’234.50", None, 1234.5),
+            ("ab 20 EUR", None, 20.0),
+            ("20,- €", None, 20.0),
+            ("12,5", None, 12.5),
+            ("12.50", None, 12.5),
+            ("0.500", None, 0.5),
+            ("1.234.567", None, 1234567.0),
+            ("1,234,567.89", None, 1_234_567.9),
+            ("1.234.567,89", None, 1_234_567.9),
+            ("1 234 567 €", None, 1234567.0),
+            ("1 234,56 €", None, 1234.56),
+            ("1\u{a0}234,56\u{a0}€", None, 1234.56),
+            ("1\u{202f}234,56 €", None, 1234.56),
+            ("Preis: 85 €", None, 85.0),
+            ("€85", None, 85.0),
+            ("1.234", Some(DecimalComma), 1234.0),
+            ("1.234", Some(DecimalPoint), 1.234),
+            ("1,234", Some(DecimalComma), 1.234),
+            ("1,234", Some(DecimalPoint), 1234.0),
+            ("1.234,-", Some(DecimalComma), 1234.0),
+            ("  42  ", None, 42.0),
+            ("EUR 1.299,00 *", None, 1299.0),
+            ("US$1,299.99", None, 1299.99),
+        ];
+
+        for (text, format, expected) in cases {
+            assert_eq!(
+                parse_amount(text, format),
+                Ok(expected),
+                "parsing '{}'",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_unparseable_amounts() {
+        let cases = vec![
+            ("auf Anfrage", "NoAmount"),
+            ("", "NoAmount"),
+            ("1.234", "Ambiguous"),
+            ("1,234 €", "Ambiguous"),
+            ("20 - 30 €", "Ambiguous"),
+            ("5 10 €", "Ambiguous"),
+            ("1234 567 €", "Ambiguous"),
+            ("12 34,50 €", "Ambiguous"),
+            ("statt 30 € nur 20 €", "Ambiguous"),
+            ("1.23.4", "InvalidAmount"),
+            ("1,234.5,6", "InvalidAmount"),
+            ("12.34,567.8", "InvalidAmount"),
+        ];
+
+        for (text, expected) in cases {
+            let actual = parse_amount(text, None);
+            let kind = match &actual {
+                Err(PriceParseError::NoAmount { .. }) => "NoAmount",
+                Err(PriceParseError::Ambiguous { .. }) => "Ambiguous",
+                Err(PriceParseError::InvalidAmount { .. }) => "InvalidAmount",
+                _ => "other",
+            };
+            assert_eq!(kind, expected, "parsing '{}' gave {:?}", text, actual);
+        }
+    }
+
+    type PriceCase = (
+        &'static str,
+        Option<Currency>,
+        Option<Language>,
+        Result<Price, PriceParseError>,
+    );
+
+    #[test]
+    fn should_parse_prices_with_hints() {
+        let cases: Vec<PriceCase> = vec![
+            ("1.234,56 €", None, None, Ok(Price::new(EUR, 1234.56))),
+            ("1.234 €", None, Some(DE), Ok(Price::new(EUR, 1234.0))),
+            ("1.234 €", None, Some(EN), Ok(Price::new(EUR, 1.234))),
+            ("99,90", Some(EUR), None, Ok(Price::new(EUR, 99.9))),
+            ("ab 20 EUR", Some(EUR), Some(DE), Ok(Price::new(EUR, 20.0))),
+            ("1,234 $", None, None, Ok(Price::new(USD, 1234.0))),
+        ];
+
+        for (text, currency, language, expected) in cases {
+            assert_eq!(
+                parse_price(text, currency.as_ref(), language.as_ref()),
+                expected,
+                "parsing '{}'",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn should_require_a_currency() {
+        assert_eq!(
+            parse_price("99,90", None, Some(&DE)),
+            Err(PriceParseError::MissingCurrency {
+                text: "99,90".to_string()
+            })
+        );
+        assert!(matches!(
+            parse_price("20 € / 25 $", None, None),
+            Err(PriceParseError::Ambiguous { .. })
+        ));
+    }
+}
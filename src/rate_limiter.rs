@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tokio::time::{Instant, sleep};
+use tracing::{debug, warn};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct RateLimit {
+    #[serde(rename = "requestsPerSecond")]
+    pub requests_per_second: f64,
+
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+}
+
+fn default_burst() -> u32 {
+    1
+}
+
+impl RateLimit {
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        RateLimit {
+            requests_per_second,
+            burst,
+        }
+    }
+
+    fn is_stricter_than(&self, other: &RateLimit) -> bool {
+        // a non-positive rate doesn't limit at all
+        let rate = |rate_limit: &RateLimit| match rate_limit.requests_per_second {
+            rps if rps > 0.0 => rps,
+            _ => f64::INFINITY,
+        };
+        (rate(self), self.burst.max(1)) < (rate(other), other.burst.max(1))
+    }
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate_limit: RateLimit,
+    state: Mutex<BucketState>,
+}
+
+impl TokenBucket {
+    pub fn new(rate_limit: RateLimit) -> Self {
+        let burst = rate_limit.burst.max(1) as f64;
+        TokenBucket {
+            rate_limit,
+            state: Mutex::new(BucketState {
+                tokens: burst,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn rate_limit(&self) -> &RateLimit {
+        &self.rate_limit
+    }
+
+    pub async fn acquire(&self) {
+        loop {
+            let wait = match self.try_acquire() {
+                Ok(()) => return,
+                Err(wait) => wait,
+            };
+            sleep(wait).await;
+        }
+    }
+
+    fn try_acquire(&self) -> Result<(), Duration> {
+        let rate = self.rate_limit.requests_per_second;
+        if rate <= 0.0 {
+            return Ok(());
+        }
+        let burst = self.rate_limit.burst.max(1) as f64;
+
+        let mut state = self
+            .state
+            .lock()
+            .expect("token bucket lock shouldn't be poisoned");
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * rate).min(burst);
+        state.last_refill = now;
+
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - state.tokens) / rate))
+        }
+    }
+}
+
+static HOST_BUCKETS: LazyLock<Mutex<HashMap<String, Arc<TokenBucket>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The strictest [`RateLimit`] registered for a host wins, so scrapers hitting the same
+/// shop cannot loosen each other's limits, while a tightened config applies right away.
+pub fn bucket_for_url(url: &str, rate_limit: &RateLimit) -> Option<Arc<TokenBucket>> {
+    let host = reqwest::Url::parse(url).ok()?.host_str()?.to_lowercase();
+    let mut buckets = HOST_BUCKETS
+        .lock()
+        .expect("host bucket lock shouldn't be poisoned");
+    let bucket = buckets
+        .entry(host.clone())
+        .or_insert_with(|| Arc::new(TokenBucket::new(rate_limit.clone())));
+    if rate_limit.is_stricter_than(bucket.rate_limit()) {
+        warn!(
+            host = %host,
+            registered = ?bucket.rate_limit(),
+            requested = ?rate_limit,
+            "Tightening the rate limit of host."
+        );
+        *bucket = Arc::new(TokenBucket::new(rate_limit.clone()));
+    } else if bucket.rate_limit() != rate_limit {
+        debug!(
+            host = %host,
+            registered = ?bucket.rate_limit(),
+            requested = ?rate_limit,
+            "Host already has a stricter rate limit, keeping the registered one."
+        );
+    }
+    Some(bucket.clone())
+}
+
+pub async fn acquire_for_url(url: &str, rate_limit: &RateLimit) {
+    if let Some(bucket) = bucket_for_url(url, rate_limit) {
+        bucket.acquire().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rate_limiter::{RateLimit, TokenBucket, bucket_for_url};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[tokio::test]
+    async fn should_allow_burst_then_pace_requests() {
+        let bucket = TokenBucket::new(RateLimit::new(20.0, 3));
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            bucket.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        bucket.acquire().await;
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[test]
+    fn should_share_bucket_per_host() {
+        let rate_limit = RateLimit::new(1.0, 1);
+
+        let a = bucket_for_url("https://shop.example.com/list?page=1", &rate_limit).unwrap();
+        let b = bucket_for_url("https://SHOP.example.com/item/42", &rate_limit).unwrap();
+        let c = bucket_for_url("https://other.example.com/", &rate_limit).unwrap();
+
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+    }
+
+    #[test]
+    fn should_keep_the_stricter_rate_limit_of_a_host() {
+        let url = "https://strict.example.com/list";
+
+        let loose = bucket_for_url(url, &RateLimit::new(10.0, 5)).unwrap();
+        let strict = bucket_for_url(url, &RateLimit::new(2.0, 1)).unwrap();
+        let looser_again = bucket_for_url(url, &RateLimit::new(10.0, 5)).unwrap();
+
+        assert_eq!(loose.rate_limit(), &RateLimit::new(10.0, 5));
+        assert_eq!(strict.rate_limit(), &RateLimit::new(2.0, 1));
+        assert!(Arc::ptr_eq(&strict, &looser_again));
+    }
+
+    #[test]
+    fn should_not_create_bucket_for_invalid_url() {
+        assert!(bucket_for_url("not a url", &RateLimit::new(1.0, 1)).is_none());
+    }
+}
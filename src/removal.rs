@@ -0,0 +1,155 @@
+use crate::change_detection::{ItemDiff, ItemField, ItemFingerprint, detect_changes};
+use item_core::item_data::ItemData;
+use item_core::item_state::ItemState;
+use std::collections::{HashMap, HashSet};
+
+pub fn removed_item_diffs(
+    source_id: &str,
+    item_id_fingerprint_map: &HashMap<String, ItemFingerprint>,
+    scraped_item_ids: &HashSet<String>,
+    removed_state: &ItemState,
+) -> Vec<ItemDiff> {
+    let items = item_id_fingerprint_map
+        .iter()
+        .filter(|(item_id, fingerprint)| {
+            !scraped_item_ids.contains(item_id.as_str())
+                && fingerprint.state.as_ref() != Some(removed_state)
+        })
+        .map(|(item_id, _)| {
+            ItemData::new(item_id.clone())
+                .source_id(source_id.to_string())
+                .state(removed_state.clone())
+                .to_owned()
+        })
+        .collect::<Vec<_>>();
+
+    detect_changes(items, item_id_fingerprint_map, &[ItemField::State])
+}
+
+/// Items removed by earlier runs don't count as live.
+pub fn removal_ratio(
+    removed_diffs: &[ItemDiff],
+    item_id_fingerprint_map: &HashMap<String, ItemFingerprint>,
+    scraped_item_ids: &HashSet<String>,
+) -> f64 {
+    let still_live = scraped_item_ids
+        .iter()
+        .filter(|item_id| item_id_fingerprint_map.contains_key(item_id.as_str()))
+        .count();
+    let live = removed_diffs.len() + still_live;
+    if live == 0 {
+        0.0
+    } else {
+        removed_diffs.len() as f64 / live as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::change_detection::{ItemField, ItemFingerprint};
+    use crate::removal::{removal_ratio, removed_item_diffs};
+    use item_core::item_hash::hash_item_details;
+    use item_core::item_state::ItemState::{AVAILABLE, REMOVED};
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn should_only_remove_items_missing_from_scrape() {
+        let fingerprint = ItemFingerprint {
+            state: Some(AVAILABLE),
+            ..ItemFingerprint::from_hash(hash_item_details(Some(AVAILABLE), Some(42f32)))
+        };
+        let hashes = HashMap::from([
+            ("https://foo.bar#1".to_string(), fingerprint.clone()),
+            ("https://foo.bar#2".to_string(), fingerprint),
+        ]);
+        let scraped = HashSet::from(["https://foo.bar#1".to_string()]);
+
+        let actual = removed_item_diffs("https://foo.bar", &hashes, &scraped, &REMOVED);
+
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].item.item_id, "https://foo.bar#2");
+        assert_eq!(actual[0].item.state, Some(REMOVED));
+        assert_eq!(
+            actual[0].item.source_id,
+            Some("https://foo.bar".to_string())
+        );
+        assert_eq!(actual[0].changed_fields, vec![ItemField::State]);
+    }
+
+    #[test]
+    fn should_not_remove_items_already_removed() {
+        let hashes = HashMap::from([(
+            "https://foo.bar#1".to_string(),
+            ItemFingerprint::from_hash(hash_item_details(Some(REMOVED), None)),
+        )]);
+
+        let actual = removed_item_diffs("https://foo.bar", &hashes, &HashSet::new(), &REMOVED);
+
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn should_not_remove_items_already_removed_with_price() {
+        let hashes = HashMap::from([(
+            "https://foo.bar#1".to_string(),
+            ItemFingerprint {
+                state: Some(REMOVED),
+                ..ItemFingerprint::from_hash(hash_item_details(Some(REMOVED), Some(42f32)))
+            },
+        )]);
+
+        let actual = removed_item_diffs("https://foo.bar", &hashes, &HashSet::new(), &REMOVED);
+
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn should_compute_removal_ratio_of_live_items() {
+        let fingerprint = ItemFingerprint::from_hash(hash_item_details(Some(AVAILABLE), None));
+        let hashes = (1..=4)
+            .map(|i| (format!("https://foo.bar#{}", i), fingerprint.clone()))
+            .chain([(
+                "https://foo.bar#5".to_string(),
+                ItemFingerprint::from_hash(hash_item_details(Some(REMOVED), None)),
+            )])
+            .collect::<HashMap<_, _>>();
+        let scraped = HashSet::from([
+            "https://foo.bar#1".to_string(),
+            "https://foo.bar#new".to_string(),
+        ]);
+        let removed = removed_item_diffs("https://foo.bar", &hashes, &scraped, &REMOVED);
+
+        let actual = removal_ratio(&removed, &hashes, &scraped);
+
+        assert_eq!(removed.len(), 3);
+        assert_eq!(actual, 0.75);
+        assert_eq!(removal_ratio(&[], &HashMap::new(), &HashSet::new()), 0.0);
+    }
+
+    #[test]
+    fn should_not_count_items_removed_with_price_as_live() {
+        let hashes = HashMap::from([
+            (
+                "https://foo.bar#1".to_string(),
+                ItemFingerprint {
+                    state: Some(AVAILABLE),
+                    ..ItemFingerprint::from_hash(hash_item_details(Some(AVAILABLE), Some(42f32)))
+                },
+            ),
+            (
+                "https://foo.bar#2".to_string(),
+                ItemFingerprint {
+                    state: Some(REMOVED),
+                    ..ItemFingerprint::from_hash(hash_item_details(Some(REMOVED), Some(42f32)))
+                },
+            ),
+        ]);
+        let scraped = HashSet::from(["https://foo.bar#1".to_string()]);
+        let removed = removed_item_diffs("https://foo.bar", &hashes, &scraped, &REMOVED);
+
+        let actual = removal_ratio(&removed, &hashes, &scraped);
+
+        assert!(removed.is_empty());
+        assert_eq!(actual, 0.0);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::change_detection::ItemDiff;
+use crate::pagination::Page;
+use crate::scraper::ScrapeError;
+use crate::scraper_config::ScraperConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct PushFailure {
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+
+    pub reason: String,
+}
+
+impl PushFailure {
+    pub fn new(item_id: String, reason: String) -> Self {
+        PushFailure { item_id, reason }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct PushResult {
+    pub pushed: usize,
+    pub failures: Vec<PushFailure>,
+}
+
+/// Scraping and pushing run interleaved, so they share one phase.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct PhaseTimings {
+    #[serde(rename = "loadHashesMillis")]
+    pub load_hashes_millis: u64,
+
+    #[serde(rename = "scrapeAndPushMillis")]
+    pub scrape_and_push_millis: u64,
+
+    #[serde(rename = "removalMillis")]
+    pub removal_millis: u64,
+
+    #[serde(rename = "totalMillis")]
+    pub total_millis: u64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct ScrapeReport {
+    #[serde(rename = "pagesScraped")]
+    pub pages_scraped: usize,
+
+    #[serde(rename = "itemsScraped")]
+    pub items_scraped: usize,
+
+    #[serde(rename = "itemsUnchanged")]
+    pub items_unchanged: usize,
+
+    #[serde(rename = "itemsRemoved")]
+    pub items_removed: usize,
+
+    /// Removals not pushed because they exceeded `maxRemovalRatio`.
+    #[serde(rename = "removalsWithheld", default)]
+    pub removals_withheld: usize,
+
+    #[serde(rename = "itemsPushed")]
+    pub items_pushed: usize,
+
+    #[serde(rename = "pushFailures")]
+    pub push_failures: Vec<PushFailure>,
+
+    #[serde(rename = "scrapeErrors")]
+    pub scrape_errors: HashMap<String, usize>,
+
+    /// Items pushed as scraped because enriching them failed.
+    #[serde(rename = "enrichErrors", default)]
+    pub enrich_errors: usize,
+
+    /// Count of items that couldn't be scraped by [`ScrapeError::kind`], their pages were scraped.
+    #[serde(
+        rename = "itemErrors",
+        skip_serializing_if = "HashMap::is_empty",
+        default
+    )]
+    pub item_errors: HashMap<String, usize>,
+
+    pub timings: PhaseTimings,
+
+    /// What would have been pushed in a dry run, where `items_pushed` stays 0.
+    #[serde(rename = "wouldPush", skip_serializing_if = "Vec::is_empty", default)]
+    pub would_push: Vec<ItemDiff>,
+
+    /// The config to invoke the handler with next, if the run stopped before the deadline.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub continuation: Option<ScraperConfig>,
+}
+
+impl ScrapeReport {
+    pub fn record_page(&mut self, page_result: &Result<Page, ScrapeError>) {
+        match page_result {
+            Ok(page) => {
+                self.pages_scraped += 1;
+                self.items_scraped += page.items.len();
+                for err in &page.errors {
+                    *self.item_errors.entry(err.kind().to_string()).or_default() += 1;
+                }
+            }
+            Err(e) => self.record_scrape_error(e),
+        }
+    }
+
+    pub fn record_scrape_error(&mut self, err: &ScrapeError) {
+        *self
+            .scrape_errors
+            .entry(err.kind().to_string())
+            .or_default() += 1;
+    }
+
+    pub fn record_push(&mut self, push_result: PushResult) {
+        self.items_pushed += push_result.pushed;
+        self.push_failures.extend(push_result.failures);
+    }
+
+    pub fn push_loss_ratio(&self) -> f64 {
+        let attempted = self.items_pushed + self.push_failures.len();
+        if attempted == 0 {
+            0.0
+        } else {
+            self.push_failures.len() as f64 / attempted as f64
+        }
+    }
+
+    pub fn has_scrape_errors(&self) -> bool {
+        !self.scrape_errors.is_empty()
+    }
+
+    pub fn has_item_errors(&self) -> bool {
+        !self.item_errors.is_empty()
+    }
+
+    pub fn scrape_error_count(&self) -> usize {
+        self.scrape_errors.values().sum()
+    }
+}
+
+pub(crate) fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pagination::Page;
+    use crate::report::{PushFailure, PushResult, ScrapeReport};
+    use crate::scraper::ScrapeError;
+    use item_core::item_data::ItemData;
+    use test_api::generator::Generator;
+
+    #[test]
+    fn should_count_pages_items_and_errors_by_kind() {
+        let mut report = ScrapeReport::default();
+
+        report.record_page(&Ok(Page::numbered(ItemData::generate_many(3), 1)));
+        report.record_page(&Ok(Page::numbered(vec![], 2).with_errors(vec![
+            ScrapeError::InvalidItem {
+                item_id: "https://foo.bar#4".to_string(),
+                reason: "missing price".to_string(),
+            },
+        ])));
+        report.record_page(&Err(ScrapeError::RobotsDisallowed {
+            url: "https://foo.bar/private".to_string(),
+        }));
+
+        assert_eq!(report.pages_scraped, 2);
+        assert_eq!(report.items_scraped, 3);
+        assert_eq!(report.scrape_errors.get("RobotsDisallowed"), Some(&1));
+        assert_eq!(report.scrape_error_count(), 1);
+        assert_eq!(report.item_errors.get("InvalidItem"), Some(&1));
+    }
+
+    #[test]
+    fn should_compute_push_loss_ratio() {
+        let mut report = ScrapeReport::default();
+        assert_eq!(report.push_loss_ratio(), 0.0);
+
+        report.record_push(PushResult {
+            pushed: 3,
+            failures: vec![PushFailure::new(
+                "https://foo.bar#1".to_string(),
+                "InternalError".to_string(),
+            )],
+        });
+
+        assert_eq!(report.items_pushed, 3);
+        assert_eq!(report.push_loss_ratio(), 0.25);
+    }
+
+    #[test]
+    fn should_serialize_camel_case() {
+        let json = serde_json::to_value(ScrapeReport::default()).unwrap();
+
+        assert_eq!(json["itemsPushed"], 0);
+        assert_eq!(json["timings"]["totalMillis"], 0);
+    }
+}
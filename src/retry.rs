@@ -0,0 +1,260 @@
+use crate::scraper::ScrapeError;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum RetryCondition {
+    Timeout,
+    Connect,
+    ServerError,
+    Decode,
+}
+
+/// `max_attempts` includes the first attempt, so `1` disables retrying.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(default)]
+pub struct RetryPolicy {
+    #[serde(rename = "maxAttempts")]
+    pub max_attempts: u32,
+
+    #[serde(rename = "backoffBaseMillis")]
+    pub backoff_base_millis: u64,
+
+    #[serde(rename = "backoffCapMillis")]
+    pub backoff_cap_millis: u64,
+
+    pub jitter: bool,
+
+    #[serde(rename = "retryOn")]
+    pub retry_on: Vec<RetryCondition>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            backoff_base_millis: 500,
+            backoff_cap_millis: 30_000,
+            jitter: true,
+            retry_on: vec![
+                RetryCondition::Timeout,
+                RetryCondition::Connect,
+                RetryCondition::ServerError,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    pub fn backoff(&self, retry: u32) -> Duration {
+        backoff(
+            self.backoff_base_millis,
+            self.backoff_cap_millis,
+            self.jitter,
+            retry,
+        )
+    }
+
+    pub fn is_retryable(&self, err: &ScrapeError) -> bool {
+        match err {
+            ScrapeError::ReqwestError(err) => {
+                self.retry_on.iter().any(|condition| match condition {
+                    RetryCondition::Timeout => err.is_timeout(),
+                    RetryCondition::Connect => err.is_connect(),
+                    RetryCondition::ServerError => {
+                        err.status().is_some_and(|status| status.is_server_error())
+                    }
+                    RetryCondition::Decode => err.is_decode() || err.is_body(),
+                })
+            }
+            ScrapeError::HttpStatus { status, .. } => {
+                (500..600).contains(status) && self.retry_on.contains(&RetryCondition::ServerError)
+            }
+            ScrapeError::Timeout { .. } => self.retry_on.contains(&RetryCondition::Timeout),
+            // throttling is handled by the adaptive pacing rather than by retrying
+            ScrapeError::RateLimited { .. } => false,
+            ScrapeError::RobotsDisallowed { .. } => false,
+            ScrapeError::Parse { .. } => false,
+            ScrapeError::Blocked { .. } => false,
+            ScrapeError::InvalidItem { .. } => false,
+            ScrapeError::Custom(_) => false,
+        }
+    }
+
+    // region fluent_setter
+
+    pub fn max_attempts(&mut self, max_attempts: u32) -> &mut Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn backoff_base_millis(&mut self, backoff_base_millis: u64) -> &mut Self {
+        self.backoff_base_millis = backoff_base_millis;
+        self
+    }
+
+    pub fn backoff_cap_millis(&mut self, backoff_cap_millis: u64) -> &mut Self {
+        self.backoff_cap_millis = backoff_cap_millis;
+        self
+    }
+
+    pub fn jitter(&mut self, jitter: bool) -> &mut Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn retry_on(&mut self, retry_on: Vec<RetryCondition>) -> &mut Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    // endregion
+}
+
+/// Retries of pushing to SQS. Unlike [`RetryPolicy`], what is retried is fixed by the sink.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(default)]
+pub struct PushRetryPolicy {
+    #[serde(rename = "maxAttempts")]
+    pub max_attempts: u32,
+
+    #[serde(rename = "backoffBaseMillis")]
+    pub backoff_base_millis: u64,
+
+    #[serde(rename = "backoffCapMillis")]
+    pub backoff_cap_millis: u64,
+
+    pub jitter: bool,
+}
+
+impl Default for PushRetryPolicy {
+    fn default() -> Self {
+        PushRetryPolicy {
+            max_attempts: 3,
+            backoff_base_millis: 500,
+            backoff_cap_millis: 30_000,
+            jitter: true,
+        }
+    }
+}
+
+impl PushRetryPolicy {
+    pub fn backoff(&self, retry: u32) -> Duration {
+        backoff(
+            self.backoff_base_millis,
+            self.backoff_cap_millis,
+            self.jitter,
+            retry,
+        )
+    }
+
+    // region fluent_setter
+
+    pub fn max_attempts(&mut self, max_attempts: u32) -> &mut Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn backoff_base_millis(&mut self, backoff_base_millis: u64) -> &mut Self {
+        self.backoff_base_millis = backoff_base_millis;
+        self
+    }
+
+    pub fn backoff_cap_millis(&mut self, backoff_cap_millis: u64) -> &mut Self {
+        self.backoff_cap_millis = backoff_cap_millis;
+        self
+    }
+
+    pub fn jitter(&mut self, jitter: bool) -> &mut Self {
+        self.jitter = jitter;
+        self
+    }
+
+    // endregion
+}
+
+fn backoff(base_millis: u64, cap_millis: u64, jitter: bool, retry: u32) -> Duration {
+    let exponent = retry.saturating_sub(1).min(63);
+    let millis = base_millis.saturating_mul(1u64 << exponent).min(cap_millis);
+    let millis = if jitter && millis > 0 {
+        rand::rng().random_range(0..=millis)
+    } else {
+        millis
+    };
+    Duration::from_millis(millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::retry::{RetryCondition, RetryPolicy};
+    use crate::scraper::ScrapeError;
+    use std::time::Duration;
+
+    #[test]
+    fn should_double_backoff_until_cap() {
+        let policy = RetryPolicy::default()
+            .backoff_base_millis(100)
+            .backoff_cap_millis(1_000)
+            .jitter(false)
+            .clone();
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_millis(1_000));
+        assert_eq!(policy.backoff(99), Duration::from_millis(1_000));
+    }
+
+    #[test]
+    fn should_keep_jittered_backoff_within_bounds() {
+        let policy = RetryPolicy::default()
+            .backoff_base_millis(100)
+            .backoff_cap_millis(1_000)
+            .jitter(true)
+            .clone();
+
+        for retry in 1..10 {
+            assert!(policy.backoff(retry) <= Duration::from_millis(1_000));
+        }
+    }
+
+    #[test]
+    fn should_fill_missing_fields_with_defaults() {
+        let policy: RetryPolicy = serde_json::from_str(r#"{"maxAttempts":5}"#).unwrap();
+
+        assert_eq!(policy.max_attempts, 5);
+        assert_eq!(policy.retry_on, RetryPolicy::default().retry_on);
+    }
+
+    #[test]
+    fn should_retry_server_errors_and_timeouts_only() {
+        let policy = RetryPolicy::default();
+        let status = |status| ScrapeError::HttpStatus {
+            status,
+            url: "https://foo.bar".to_string(),
+        };
+        let timeout = ScrapeError::Timeout {
+            url: "https://foo.bar".to_string(),
+        };
+
+        assert!(policy.is_retryable(&status(502)));
+        assert!(!policy.is_retryable(&status(404)));
+        assert!(policy.is_retryable(&timeout));
+        assert!(!policy.is_retryable(&ScrapeError::custom("oops")));
+        assert!(
+            !policy
+                .clone()
+                .retry_on(vec![RetryCondition::ServerError])
+                .is_retryable(&timeout)
+        );
+    }
+}
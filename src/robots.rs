@@ -0,0 +1,306 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(default)]
+pub struct RobotsPolicy {
+    /// The product token matched against `User-agent` lines, e.g. `blitzfilter`.
+    #[serde(rename = "userAgent")]
+    pub user_agent: String,
+}
+
+impl Default for RobotsPolicy {
+    fn default() -> Self {
+        RobotsPolicy {
+            user_agent: "blitzfilter".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Group {
+    user_agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RobotsTxt {
+    groups: Vec<Group>,
+}
+
+impl RobotsTxt {
+    pub fn allow_all() -> Self {
+        RobotsTxt { groups: vec![] }
+    }
+
+    pub fn disallow_all() -> Self {
+        RobotsTxt {
+            groups: vec![Group {
+                user_agents: vec!["*".to_string()],
+                rules: vec![Rule {
+                    allow: false,
+                    pattern: "/".to_string(),
+                }],
+                crawl_delay: None,
+            }],
+        }
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut groups: Vec<Group> = vec![];
+        let mut current: Option<Group> = None;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    // consecutive user-agent lines share one group, any other line closes it
+                    let starts_new_group = current
+                        .as_ref()
+                        .is_none_or(|group| !group.rules.is_empty() || group.crawl_delay.is_some());
+                    if starts_new_group {
+                        groups.extend(current.take());
+                        current = Some(Group::default());
+                    }
+                    if let Some(group) = current.as_mut() {
+                        group.user_agents.push(value.to_lowercase());
+                    }
+                }
+                "allow" | "disallow" => {
+                    if let Some(group) = current.as_mut() {
+                        // an empty disallow allows everything, so it can be ignored
+                        if !value.is_empty() {
+                            group.rules.push(Rule {
+                                allow: key == "allow",
+                                pattern: value.to_string(),
+                            });
+                        }
+                    }
+                }
+                "crawl-delay" => {
+                    if let Some(group) = current.as_mut() {
+                        group.crawl_delay = value
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                            .map(Duration::from_secs_f64);
+                    }
+                }
+                _ => {}
+            }
+        }
+        groups.extend(current);
+
+        RobotsTxt { groups }
+    }
+
+    /// All groups addressing `user_agent`, or the `*` groups if none does.
+    fn groups_for(&self, user_agent: &str) -> Vec<&Group> {
+        let user_agent = user_agent.to_lowercase();
+        let product_token = user_agent.split('/').next().unwrap_or_default().trim();
+        let specific = self
+            .groups
+            .iter()
+            .filter(|group| group.user_agents.iter().any(|ua| ua == product_token))
+            .collect::<Vec<_>>();
+        if !specific.is_empty() {
+            return specific;
+        }
+        self.groups
+            .iter()
+            .filter(|group| group.user_agents.iter().any(|ua| ua == "*"))
+            .collect()
+    }
+
+    /// Whether `path` (including the query) may be requested. The longest matching rule wins,
+    /// `Allow` wins ties.
+    pub fn is_allowed(&self, user_agent: &str, path: &str) -> bool {
+        if path == "/robots.txt" {
+            return true;
+        }
+        self.groups_for(user_agent)
+            .into_iter()
+            .flat_map(|group| group.rules.iter())
+            .filter(|rule| matches_pattern(&rule.pattern, path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+
+    pub fn is_url_allowed(&self, user_agent: &str, url: &Url) -> bool {
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        self.is_allowed(user_agent, &path)
+    }
+
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<Duration> {
+        self.groups_for(user_agent)
+            .into_iter()
+            .filter_map(|group| group.crawl_delay)
+            .max()
+    }
+}
+
+/// Matches a robots.txt path pattern supporting `*` and a trailing `$` against the start of `path`.
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = parts.next().and_then(|first| path.strip_prefix(first)) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    if parts.is_empty() {
+        return !anchored || rest.is_empty();
+    }
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+static ROBOTS_CACHE: LazyLock<Mutex<HashMap<String, Arc<RobotsTxt>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// A missing robots.txt (4xx) allows everything. An unreachable one (5xx, network errors)
+/// disallows everything and isn't cached process-wide, so the next run tries again.
+pub async fn robots_txt_for_url(url: &Url, client: &reqwest::Client) -> Arc<RobotsTxt> {
+    let origin = url.origin().ascii_serialization();
+    let cached = ROBOTS_CACHE
+        .lock()
+        .expect("robots cache lock shouldn't be poisoned")
+        .get(&origin)
+        .cloned();
+    if let Some(robots_txt) = cached {
+        return robots_txt;
+    }
+
+    let robots_url = format!("{}/robots.txt", origin);
+    let response = client.get(&robots_url).send().await;
+    let robots_txt = match response {
+        Ok(response) if response.status().is_success() => match response.text().await {
+            Ok(content) => RobotsTxt::parse(&content),
+            Err(e) => {
+                warn!(
+                    url = %robots_url,
+                    error = %e,
+                    "Reading robots.txt failed, disallowing all."
+                );
+                return Arc::new(RobotsTxt::disallow_all());
+            }
+        },
+        Ok(response) if response.status().is_client_error() => RobotsTxt::allow_all(),
+        Ok(response) => {
+            warn!(
+                url = %robots_url,
+                status = response.status().as_u16(),
+                "Unreachable robots.txt, disallowing all."
+            );
+            return Arc::new(RobotsTxt::disallow_all());
+        }
+        Err(e) => {
+            warn!(
+                url = %robots_url,
+                error = %e,
+                "Fetching robots.txt failed, disallowing all."
+            );
+            return Arc::new(RobotsTxt::disallow_all());
+        }
+    };
+    info!(url = %robots_url, "Fetched robots.txt.");
+
+    let robots_txt = Arc::new(robots_txt);
+    ROBOTS_CACHE
+        .lock()
+        .expect("robots cache lock shouldn't be poisoned")
+        .insert(origin, robots_txt.clone());
+    robots_txt
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::robots::RobotsTxt;
+    use std::time::Duration;
+
+    const ROBOTS_TXT: &str = r#"
+# shop robots
+User-agent: *
+Disallow: /checkout
+Disallow: /*?sort=
+Allow: /checkout/help
+Crawl-delay: 2
+
+User-agent: blitzfilter
+User-agent: other-bot
+Disallow: /private/
+Disallow: /*.pdf$
+Crawl-delay: 0.5
+
+User-agent: evil-bot
+Disallow: /
+"#;
+
+    #[test]
+    fn should_apply_wildcard_group_to_unknown_agents() {
+        let robots_txt = RobotsTxt::parse(ROBOTS_TXT);
+
+        assert!(!robots_txt.is_allowed("somebot", "/checkout/cart"));
+        assert!(robots_txt.is_allowed("somebot", "/checkout/help"));
+        assert!(!robots_txt.is_allowed("somebot", "/shop?sort=price"));
+        assert!(robots_txt.is_allowed("somebot", "/shop?page=2"));
+        assert_eq!(
+            robots_txt.crawl_delay("somebot"),
+            Some(Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn should_apply_only_most_specific_group() {
+        let robots_txt = RobotsTxt::parse(ROBOTS_TXT);
+
+        assert!(robots_txt.is_allowed("Blitzfilter/1.0", "/checkout/cart"));
+        assert!(!robots_txt.is_allowed("Blitzfilter/1.0", "/private/item"));
+        assert!(!robots_txt.is_allowed("blitzfilter", "/catalog.pdf"));
+        assert!(robots_txt.is_allowed("blitzfilter", "/catalog.pdf?download=1"));
+        assert_eq!(
+            robots_txt.crawl_delay("blitzfilter"),
+            Some(Duration::from_millis(500))
+        );
+        assert!(!robots_txt.is_allowed("evil-bot", "/"));
+    }
+
+    #[test]
+    fn should_allow_everything_without_rules() {
+        let robots_txt = RobotsTxt::parse("User-agent: *\nDisallow:\n");
+
+        assert!(robots_txt.is_allowed("blitzfilter", "/anything"));
+        assert!(RobotsTxt::allow_all().is_allowed("blitzfilter", "/anything"));
+        assert!(!RobotsTxt::disallow_all().is_allowed("blitzfilter", "/anything"));
+    }
+}
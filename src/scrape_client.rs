@@ -0,0 +1,231 @@
+use crate::rate_limiter::{RateLimit, acquire_for_url};
+use crate::robots::{RobotsPolicy, RobotsTxt, robots_txt_for_url};
+use crate::scraper::ScrapeError;
+use crate::scraper_config::ScraperConfig;
+use crate::throttle::check_rate_limit;
+use reqwest::header::HeaderMap;
+use reqwest::{Body, IntoUrl, Method, Request, RequestBuilder, Response, Url};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Checks every request against robots.txt and waits for the rate limit of its host.
+/// 429 and 503 responses fail as [`ScrapeError::RateLimited`], so the scraping loop slows down.
+#[derive(Debug, Clone)]
+pub struct ScrapeClient {
+    client: reqwest::Client,
+    rate_limit: Option<RateLimit>,
+    robots_policy: Option<RobotsPolicy>,
+    // also holds robots.txt that failed to load, so they aren't fetched again for every request
+    robots_txts: Arc<Mutex<HashMap<String, Arc<RobotsTxt>>>>,
+}
+
+impl ScrapeClient {
+    pub fn new(client: &reqwest::Client, scraper_config: &ScraperConfig) -> Self {
+        ScrapeClient {
+            client: client.clone(),
+            rate_limit: scraper_config.rate_limit.clone(),
+            robots_policy: scraper_config.robots_txt.clone(),
+            robots_txts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The underlying client, bypassing robots.txt and the rate limit.
+    pub fn inner(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    pub fn get<U: IntoUrl>(&self, url: U) -> ScrapeRequestBuilder<'_> {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post<U: IntoUrl>(&self, url: U) -> ScrapeRequestBuilder<'_> {
+        self.request(Method::POST, url)
+    }
+
+    pub fn request<U: IntoUrl>(&self, method: Method, url: U) -> ScrapeRequestBuilder<'_> {
+        ScrapeRequestBuilder {
+            client: self,
+            builder: self.client.request(method, url),
+        }
+    }
+
+    /// Fetched at most once per client, failures included.
+    pub async fn robots_txt(&self, url: &Url) -> Arc<RobotsTxt> {
+        let origin = url.origin().ascii_serialization();
+        let mut robots_txts = self.robots_txts.lock().await;
+        if let Some(robots_txt) = robots_txts.get(&origin) {
+            return robots_txt.clone();
+        }
+        let robots_txt = robots_txt_for_url(url, &self.client).await;
+        robots_txts.insert(origin, robots_txt.clone());
+        robots_txt
+    }
+
+    pub async fn execute(&self, request: Request) -> Result<Response, ScrapeError> {
+        if let Some(robots_policy) = &self.robots_policy
+            && !self
+                .robots_txt(request.url())
+                .await
+                .is_url_allowed(&robots_policy.user_agent, request.url())
+        {
+            return Err(ScrapeError::RobotsDisallowed {
+                url: request.url().to_string(),
+            });
+        }
+        if let Some(rate_limit) = &self.rate_limit {
+            acquire_for_url(request.url().as_str(), rate_limit).await;
+        }
+        check_rate_limit(self.client.execute(request).await?)
+    }
+}
+
+pub struct ScrapeRequestBuilder<'a> {
+    client: &'a ScrapeClient,
+    builder: RequestBuilder,
+}
+
+impl ScrapeRequestBuilder<'_> {
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.builder = self.builder.header(name, value);
+        self
+    }
+
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.builder = self.builder.headers(headers);
+        self
+    }
+
+    pub fn query<T: Serialize + ?Sized>(mut self, query: &T) -> Self {
+        self.builder = self.builder.query(query);
+        self
+    }
+
+    pub fn json<T: Serialize + ?Sized>(mut self, json: &T) -> Self {
+        self.builder = self.builder.json(json);
+        self
+    }
+
+    pub fn form<T: Serialize + ?Sized>(mut self, form: &T) -> Self {
+        self.builder = self.builder.form(form);
+        self
+    }
+
+    pub fn body<T: Into<Body>>(mut self, body: T) -> Self {
+        self.builder = self.builder.body(body);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.builder = self.builder.timeout(timeout);
+        self
+    }
+
+    pub async fn send(self) -> Result<Response, ScrapeError> {
+        self.client.execute(self.builder.build()?).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rate_limiter::RateLimit;
+    use crate::robots::{RobotsPolicy, RobotsTxt};
+    use crate::scrape_client::ScrapeClient;
+    use crate::scraper::ScrapeError;
+    use crate::scraper_config::ScraperConfig;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::time::Instant;
+
+    /// Answers every request with `response` and returns the server's base URL.
+    async fn serve(response: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await;
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn should_rate_limit_every_request() {
+        let scraper_config = ScraperConfig::new("http://127.0.0.3".to_string())
+            .rate_limit(RateLimit::new(20.0, 1))
+            .clone();
+        let client = ScrapeClient::new(&reqwest::Client::new(), &scraper_config);
+        let start = Instant::now();
+
+        // nothing listens on the discard port, so these fail right after the rate limit
+        for path in ["/list", "/item/1", "/item/2"] {
+            let url = format!("http://127.0.0.3:9{}", path);
+            assert!(client.get(url).send().await.is_err());
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    async fn should_check_every_request_against_robots_txt() {
+        let scraper_config = ScraperConfig::new("http://127.0.0.5".to_string())
+            .robots_txt(RobotsPolicy::default())
+            .clone();
+        let client = ScrapeClient::new(&reqwest::Client::new(), &scraper_config);
+        client.robots_txts.lock().await.insert(
+            "http://127.0.0.5:9".to_string(),
+            Arc::new(RobotsTxt::parse("User-agent: *\nDisallow: /item/\n")),
+        );
+
+        let disallowed = client.get("http://127.0.0.5:9/item/1").send().await;
+        let allowed = client.get("http://127.0.0.5:9/list").send().await;
+
+        assert!(matches!(
+            disallowed,
+            Err(ScrapeError::RobotsDisallowed { .. })
+        ));
+        assert!(matches!(allowed, Err(ScrapeError::ReqwestError(_))));
+    }
+
+    #[tokio::test]
+    async fn should_turn_throttling_responses_into_rate_limited() {
+        let too_many_requests =
+            serve("HTTP/1.1 429 Too Many Requests\r\nRetry-After: 7\r\nContent-Length: 0\r\n\r\n")
+                .await;
+        let unavailable =
+            serve("HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n").await;
+        let client = ScrapeClient::new(
+            &reqwest::Client::new(),
+            &ScraperConfig::new(too_many_requests.clone()),
+        );
+
+        let throttled = client
+            .get(format!("{}/list", too_many_requests))
+            .send()
+            .await;
+        let unavailable = client.get(format!("{}/list", unavailable)).send().await;
+
+        assert!(matches!(
+            throttled,
+            Err(ScrapeError::RateLimited {
+                status: 429,
+                retry_after: Some(retry_after),
+                ..
+            }) if retry_after == Duration::from_secs(7)
+        ));
+        assert!(matches!(
+            unavailable,
+            Err(ScrapeError::RateLimited {
+                status: 503,
+                retry_after: None,
+                ..
+            })
+        ));
+    }
+}
@@ -1,24 +1,82 @@
-use crate::scraper::ScrapeError::ReqwestError;
-use async_stream::try_stream;
+use crate::pagination::{Page, PageToken};
+use crate::retry::RetryPolicy;
+use crate::robots::RobotsPolicy;
+use crate::scrape_client::ScrapeClient;
+use crate::scraper::ScrapeError::{
+    Blocked, Custom, HttpStatus, InvalidItem, Parse, RateLimited, ReqwestError, RobotsDisallowed,
+    Timeout,
+};
+use crate::scraper_config::ScraperConfig;
+use crate::throttle::{AdaptivePacing, ThrottlePolicy};
+use async_stream::stream;
 pub use async_trait::async_trait;
 use futures::stream::BoxStream;
+use futures::{StreamExt, stream};
 use item_core::item_data::ItemData;
 use lambda_runtime::Diagnostic;
+use reqwest::Url;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
 use std::time::Duration;
 use tokio::time::sleep;
-use tracing::info;
+use tracing::{info, warn};
 
 #[derive(Debug)]
 pub enum ScrapeError {
     ReqwestError(reqwest::Error),
+    HttpStatus {
+        status: u16,
+        url: String,
+    },
+    Timeout {
+        url: String,
+    },
+    RateLimited {
+        status: u16,
+        url: String,
+        retry_after: Option<Duration>,
+    },
+    RobotsDisallowed {
+        url: String,
+    },
+    Parse {
+        url: String,
+        reason: String,
+    },
+    /// The shop served a captcha, bot-protection or login page instead of the listing.
+    Blocked {
+        url: String,
+        reason: String,
+    },
+    InvalidItem {
+        item_id: String,
+        reason: String,
+    },
+    Custom(Box<dyn Error + Send + Sync>),
 }
 
 impl Display for ScrapeError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             ReqwestError(err) => write!(f, "Reqwest error: {}", err),
+            HttpStatus { status, url } => write!(f, "Unexpected status {} for '{}'", status, url),
+            Timeout { url } => write!(f, "Timed out requesting '{}'", url),
+            RateLimited {
+                status,
+                url,
+                retry_after,
+            } => write!(
+                f,
+                "Rate limited with status {} for '{}', retry after: {:?}",
+                status, url, retry_after
+            ),
+            RobotsDisallowed { url } => write!(f, "Disallowed by robots.txt: '{}'", url),
+            Parse { url, reason } => write!(f, "Parsing '{}' failed: {}", url, reason),
+            Blocked { url, reason } => write!(f, "Blocked at '{}': {}", url, reason),
+            InvalidItem { item_id, reason } => {
+                write!(f, "Invalid item '{}': {}", item_id, reason)
+            }
+            Custom(err) => write!(f, "{}", err),
         }
     }
 }
@@ -27,22 +85,70 @@ impl Error for ScrapeError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             ReqwestError(err) => Some(err),
+            HttpStatus { .. } => None,
+            Timeout { .. } => None,
+            RateLimited { .. } => None,
+            RobotsDisallowed { .. } => None,
+            Parse { .. } => None,
+            Blocked { .. } => None,
+            InvalidItem { .. } => None,
+            Custom(err) => Some(err.as_ref()),
         }
     }
 }
 
+/// Splits off timeouts and status errors, e.g. of `error_for_status`, keeping the rest as is.
+/// 429 and 503 become [`ScrapeError::RateLimited`], though without the `Retry-After` of the response.
 impl From<reqwest::Error> for ScrapeError {
     fn from(value: reqwest::Error) -> Self {
-        ReqwestError(value)
+        let url = value.url().map(|url| url.to_string()).unwrap_or_default();
+        if value.is_timeout() {
+            Timeout { url }
+        } else if let Some(status) = value.status() {
+            if let 429 | 503 = status.as_u16() {
+                return RateLimited {
+                    status: status.as_u16(),
+                    url,
+                    retry_after: None,
+                };
+            }
+            HttpStatus {
+                status: status.as_u16(),
+                url,
+            }
+        } else {
+            ReqwestError(value)
+        }
+    }
+}
+
+impl ScrapeError {
+    pub fn custom(err: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Custom(err.into())
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ReqwestError(_) => "ReqwestError",
+            HttpStatus { .. } => "HttpStatus",
+            Timeout { .. } => "Timeout",
+            RateLimited { .. } => "RateLimited",
+            RobotsDisallowed { .. } => "RobotsDisallowed",
+            Parse { .. } => "Parse",
+            Blocked { .. } => "Blocked",
+            InvalidItem { .. } => "InvalidItem",
+            Custom(_) => "Custom",
+        }
     }
 }
 
 impl Into<Diagnostic> for ScrapeError {
     fn into(self) -> Diagnostic {
-        match self {
-            ReqwestError(err) => Diagnostic {
-                error_type: "ReqwestError".to_string(),
-                error_message: err.to_string(),
+        Diagnostic {
+            error_type: self.kind().to_string(),
+            error_message: match self {
+                ReqwestError(err) => err.to_string(),
+                other => other.to_string(),
             },
         }
     }
@@ -50,48 +156,267 @@ impl Into<Diagnostic> for ScrapeError {
 
 #[async_trait]
 pub trait Scraper: Send + Sync {
+    /// Only called for numbered pages, other paginations override [`Scraper::fetch_page`].
     async fn scrape_page(
+        &self,
+        _page_num: i16,
+        _client: &ScrapeClient,
+    ) -> Result<Vec<ItemData>, ScrapeError> {
+        Err(ScrapeError::custom(
+            "either `scrape_page` or `fetch_page` has to be implemented",
+        ))
+    }
+
+    /// Failed items are reported and keep removed items from being detected.
+    async fn scrape_page_results(
         &self,
         page_num: i16,
+        client: &ScrapeClient,
+    ) -> Result<Vec<Result<ItemData, ScrapeError>>, ScrapeError> {
+        let items = self.scrape_page(page_num, client).await?;
+        Ok(items.into_iter().map(Ok).collect())
+    }
+
+    fn first_page(&self) -> PageToken {
+        PageToken::first_number()
+    }
+
+    fn page_url(&self, token: &PageToken) -> Option<String> {
+        match token {
+            PageToken::NextLink { url } => Some(url.clone()),
+            _ => None,
+        }
+    }
+
+    async fn fetch_page(
+        &self,
+        token: &PageToken,
+        client: &ScrapeClient,
+    ) -> Result<Page, ScrapeError> {
+        match token {
+            PageToken::Number { page } => {
+                let results = self.scrape_page_results(*page, client).await?;
+                let next = if results.is_empty() {
+                    None
+                } else {
+                    Some(PageToken::Number { page: page + 1 })
+                };
+                Ok(Page::from_results(results, next))
+            }
+            _ => Err(ScrapeError::custom(format!(
+                "`fetch_page` has to be implemented for {}",
+                token
+            ))),
+        }
+    }
+
+    /// Only new or changed items are enriched. On error the item is pushed as scraped.
+    async fn enrich_item(
+        &self,
+        _item: &mut ItemData,
+        _client: &ScrapeClient,
+    ) -> Result<(), ScrapeError> {
+        Ok(())
+    }
+
+    fn scrape_pages(
+        &self,
         client: &reqwest::Client,
-    ) -> Result<Vec<ItemData>, ScrapeError>;
+        scraper_config: &ScraperConfig,
+    ) -> BoxStream<'_, Result<Page, ScrapeError>> {
+        let mut fetcher = PageFetcher::new(client, scraper_config);
+        let max_consecutive_failures = scraper_config
+            .circuit_breaker
+            .as_ref()
+            .and_then(|circuit_breaker| circuit_breaker.max_consecutive_page_failures)
+            .unwrap_or(1);
+        let max_pages = scraper_config.max_pages;
+        let first_page = match &scraper_config.checkpoint {
+            Some(checkpoint) => checkpoint.next_page.clone(),
+            None => self.first_page(),
+        };
+
+        Box::pin(stream! {
+            let mut token = Some(first_page);
+            let mut pages = 0;
+            let mut consecutive_failures = 0;
+            while let Some(current) = token {
+                pages += 1;
+                let next = match fetcher.fetch(self, &current).await {
+                    Ok(page) => {
+                        info!(
+                            page = %current,
+                            total = page.items.len(),
+                            failed = page.errors.len(),
+                            "Scraped page."
+                        );
+                        consecutive_failures = 0;
+                        let next = page.next.clone();
+                        yield Ok(page);
+                        next
+                    }
+                    Err(e) => {
+                        consecutive_failures += 1;
+                        let next = current
+                            .skipped()
+                            .filter(|_| consecutive_failures < max_consecutive_failures);
+                        if next.is_some() {
+                            warn!(page = %current, error = %e, "Skipping failed page.");
+                        }
+                        yield Err(e);
+                        next
+                    }
+                };
+                token = match max_pages {
+                    Some(max_pages) if pages >= max_pages => None,
+                    _ => next,
+                };
+                if token.is_some() {
+                    fetcher.pause().await;
+                }
+            }
+        })
+    }
 
     fn scrape(
         &self,
         client: &reqwest::Client,
-        sleep_between_pages_millis: Option<u64>,
-    ) -> BoxStream<Result<ItemData, ScrapeError>> {
-        let client = client.clone();
-
-        Box::pin(try_stream! {
-            let mut i: i16 = 1;
-            loop {
-                let items = self.scrape_page(i, &client).await?;
-                let items_count = items.len();
-                info!(page = i, total = items_count, "Scraped page.");
-                if items_count == 0 {
-                    break;
-                }
-                for item in items {
-                    yield item;
+        scraper_config: &ScraperConfig,
+    ) -> BoxStream<'_, Result<ItemData, ScrapeError>> {
+        Box::pin(
+            self.scrape_pages(client, scraper_config)
+                .flat_map(|page_result| {
+                    let item_results = match page_result {
+                        Ok(page) => page
+                            .items
+                            .into_iter()
+                            .map(Ok)
+                            .chain(page.errors.into_iter().map(Err))
+                            .collect::<Vec<_>>(),
+                        Err(e) => vec![Err(e)],
+                    };
+                    stream::iter(item_results)
+                }),
+        )
+    }
+}
+
+struct PageFetcher {
+    client: ScrapeClient,
+    base_url: String,
+    retry_policy: RetryPolicy,
+    throttle_policy: ThrottlePolicy,
+    pacing: AdaptivePacing,
+    robots_policy: Option<RobotsPolicy>,
+}
+
+impl PageFetcher {
+    fn new(client: &reqwest::Client, scraper_config: &ScraperConfig) -> Self {
+        let throttle_policy = scraper_config.throttle.clone().unwrap_or_default();
+        let pacing = AdaptivePacing::new(
+            Duration::from_millis(scraper_config.sleep_between_pages_millis.unwrap_or(0)),
+            &throttle_policy,
+        );
+        PageFetcher {
+            client: ScrapeClient::new(client, scraper_config),
+            base_url: scraper_config.base_url.clone(),
+            retry_policy: scraper_config
+                .retry
+                .clone()
+                .unwrap_or_else(RetryPolicy::none),
+            throttle_policy,
+            pacing,
+            robots_policy: scraper_config.robots_txt.clone(),
+        }
+    }
+
+    async fn apply_crawl_delay(&mut self) {
+        let Some(robots_policy) = &self.robots_policy else {
+            return;
+        };
+        if let Ok(base_url) = Url::parse(&self.base_url) {
+            let robots_txt = self.client.robots_txt(&base_url).await;
+            if let Some(crawl_delay) = robots_txt.crawl_delay(&robots_policy.user_agent) {
+                self.pacing.at_least(crawl_delay);
+            }
+        }
+    }
+
+    async fn fetch<S: Scraper + ?Sized>(
+        &mut self,
+        scraper: &S,
+        token: &PageToken,
+    ) -> Result<Page, ScrapeError> {
+        self.apply_crawl_delay().await;
+
+        let mut attempt = 1;
+        let mut rate_limited = 0;
+        loop {
+            match scraper.fetch_page(token, &self.client).await {
+                Ok(page) => return Ok(page),
+                Err(RateLimited {
+                    status,
+                    retry_after,
+                    ..
+                }) if rate_limited < self.throttle_policy.max_retries_per_page => {
+                    rate_limited += 1;
+                    let delay = self.pacing.slow_down(retry_after);
+                    warn!(
+                        page = %token,
+                        status,
+                        delayMillis = delay.as_millis() as u64,
+                        pacingMillis = self.pacing.delay().as_millis() as u64,
+                        "Rate limited, slowing down."
+                    );
+                    sleep(delay).await;
                 }
-                if let Some(duration) = sleep_between_pages_millis {
-                    sleep(Duration::from_millis(duration)).await;
+                Err(e)
+                    if attempt < self.retry_policy.max_attempts
+                        && self.retry_policy.is_retryable(&e) =>
+                {
+                    let delay = self.retry_policy.backoff(attempt);
+                    warn!(
+                        page = %token,
+                        attempt,
+                        delayMillis = delay.as_millis() as u64,
+                        error = %e,
+                        "Scraping page failed, retrying."
+                    );
+                    sleep(delay).await;
+                    attempt += 1;
                 }
-                i += 1;
+                Err(e) => return Err(e),
             }
-        })
+        }
+    }
+
+    async fn pause(&self) {
+        let delay = self.pacing.delay();
+        if !delay.is_zero() {
+            sleep(delay).await;
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::circuit_breaker::CircuitBreaker;
+    use crate::pagination::{Page, PageToken};
+    use crate::rate_limiter::RateLimit;
+    use crate::retry::{RetryCondition, RetryPolicy};
+    use crate::scrape_client::ScrapeClient;
     use crate::scraper::{ScrapeError, Scraper};
+    use crate::scraper_config::ScraperConfig;
+    use crate::throttle::ThrottlePolicy;
     use async_trait::async_trait;
     use futures::StreamExt;
     use item_core::item_data::ItemData;
+    use lambda_runtime::Diagnostic;
     use reqwest::Client;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::{Duration, Instant};
     use test_api::generator::Generator;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
     struct TestScraper {}
 
@@ -100,7 +425,7 @@ mod tests {
         async fn scrape_page(
             &self,
             page_num: i16,
-            _: &Client,
+            _: &ScrapeClient,
         ) -> Result<Vec<ItemData>, ScrapeError> {
             match page_num {
                 1 => Ok(ItemData::generate_many(10)),
@@ -113,8 +438,353 @@ mod tests {
     #[tokio::test]
     async fn should_scrape_all_pages_for_scrape() {
         let client = Client::new();
-        let items_count = TestScraper {}.scrape(&client, None).count().await;
+        let items_count = TestScraper {}
+            .scrape(&client, &ScraperConfig::new("https://foo.bar".to_string()))
+            .count()
+            .await;
 
         assert_eq!(items_count, 15);
     }
+
+    struct CursorTestScraper {}
+
+    #[async_trait]
+    impl Scraper for CursorTestScraper {
+        fn first_page(&self) -> PageToken {
+            PageToken::first_cursor()
+        }
+
+        async fn fetch_page(
+            &self,
+            token: &PageToken,
+            _: &ScrapeClient,
+        ) -> Result<Page, ScrapeError> {
+            match token {
+                PageToken::Cursor { cursor: None } => Ok(Page::cursor(
+                    ItemData::generate_many(3),
+                    Some("a1b2".to_string()),
+                )),
+                PageToken::Cursor {
+                    cursor: Some(cursor),
+                } if cursor == "a1b2" => Ok(Page::cursor(ItemData::generate_many(4), None)),
+                _ => panic!("unexpected page token {:?}", token),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_follow_cursors_for_scrape() {
+        let client = Client::new();
+        let items_count = CursorTestScraper {}
+            .scrape(&client, &ScraperConfig::new("https://foo.bar".to_string()))
+            .count()
+            .await;
+
+        assert_eq!(items_count, 7);
+    }
+
+    #[tokio::test]
+    async fn should_yield_one_page_per_request_for_scrape_pages() {
+        let client = Client::new();
+        let pages = TestScraper {}
+            .scrape_pages(&client, &ScraperConfig::new("https://foo.bar".to_string()))
+            .collect::<Vec<_>>()
+            .await;
+
+        // the terminating empty page is yielded as well
+        assert_eq!(pages.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn should_stop_after_max_pages() {
+        let client = Client::new();
+        let items_count = TestScraper {}
+            .scrape(
+                &client,
+                ScraperConfig::new("https://foo.bar".to_string()).max_pages(1),
+            )
+            .count()
+            .await;
+
+        assert_eq!(items_count, 10);
+    }
+
+    struct FlakyTestScraper {
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl Scraper for FlakyTestScraper {
+        async fn scrape_page(
+            &self,
+            page_num: i16,
+            client: &ScrapeClient,
+        ) -> Result<Vec<ItemData>, ScrapeError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                // nothing listens on the discard port, so this fails with a connect error
+                client.get("http://127.0.0.1:9").send().await?;
+            }
+            match page_num {
+                1 => Ok(ItemData::generate_many(10)),
+                _ => Ok(vec![]),
+            }
+        }
+    }
+
+    #[test]
+    fn should_group_diagnostics_by_kind() {
+        let cases: Vec<(ScrapeError, &str, &str)> = vec![
+            (
+                ScrapeError::HttpStatus {
+                    status: 404,
+                    url: "https://foo.bar/p/1".to_string(),
+                },
+                "HttpStatus",
+                "Unexpected status 404 for 'https://foo.bar/p/1'",
+            ),
+            (
+                ScrapeError::Blocked {
+                    url: "https://foo.bar".to_string(),
+                    reason: "captcha".to_string(),
+                },
+                "Blocked",
+                "Blocked at 'https://foo.bar': captcha",
+            ),
+            (
+                ScrapeError::InvalidItem {
+                    item_id: "https://foo.bar#1".to_string(),
+                    reason: "missing price".to_string(),
+                },
+                "InvalidItem",
+                "Invalid item 'https://foo.bar#1': missing price",
+            ),
+            (
+                ScrapeError::custom("shop is closed"),
+                "Custom",
+                "shop is closed",
+            ),
+        ];
+
+        for (err, error_type, error_message) in cases {
+            let diagnostic: Diagnostic = err.into();
+            assert_eq!(diagnostic.error_type, error_type);
+            assert_eq!(diagnostic.error_message, error_message);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_retry_failed_page_for_scrape() {
+        let client = Client::new();
+        let scraper = FlakyTestScraper {
+            calls: AtomicU32::new(0),
+        };
+        let scraper_config = ScraperConfig::new("https://foo.bar".to_string())
+            .retry(
+                RetryPolicy::default()
+                    .backoff_base_millis(1)
+                    .retry_on(vec![RetryCondition::Connect])
+                    .clone(),
+            )
+            .clone();
+
+        let items_count = scraper.scrape(&client, &scraper_config).count().await;
+
+        assert_eq!(items_count, 10);
+        assert_eq!(scraper.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn should_end_scrape_on_failed_page_without_retry_policy() {
+        let client = Client::new();
+        let scraper = FlakyTestScraper {
+            calls: AtomicU32::new(0),
+        };
+
+        let results = scraper
+            .scrape(&client, &ScraperConfig::new("https://foo.bar".to_string()))
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+
+    struct GappyTestScraper {}
+
+    #[async_trait]
+    impl Scraper for GappyTestScraper {
+        async fn scrape_page(
+            &self,
+            page_num: i16,
+            _: &ScrapeClient,
+        ) -> Result<Vec<ItemData>, ScrapeError> {
+            match page_num {
+                1 | 3 => Ok(ItemData::generate_many(4)),
+                2 => Err(ScrapeError::HttpStatus {
+                    status: 500,
+                    url: "https://foo.bar?page=2".to_string(),
+                }),
+                _ => Ok(vec![]),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_skip_failed_numbered_page_with_circuit_breaker() {
+        let client = Client::new();
+        let scraper_config = ScraperConfig::new("https://foo.bar".to_string())
+            .circuit_breaker(
+                CircuitBreaker::new()
+                    .max_consecutive_page_failures(2)
+                    .clone(),
+            )
+            .clone();
+
+        let results = GappyTestScraper {}
+            .scrape(&client, &scraper_config)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 8);
+        assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+    }
+
+    struct ThrottledTestScraper {
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl Scraper for ThrottledTestScraper {
+        async fn scrape_page(
+            &self,
+            page_num: i16,
+            _: &ScrapeClient,
+        ) -> Result<Vec<ItemData>, ScrapeError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                return Err(ScrapeError::RateLimited {
+                    status: 429,
+                    url: format!("https://foo.bar?page={}", page_num),
+                    retry_after: Some(Duration::from_millis(1)),
+                });
+            }
+            match page_num {
+                1 => Ok(ItemData::generate_many(10)),
+                _ => Ok(vec![]),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_wait_out_rate_limits_for_scrape() {
+        let client = Client::new();
+        let scraper = ThrottledTestScraper {
+            calls: AtomicU32::new(0),
+        };
+        let scraper_config = ScraperConfig::new("https://foo.bar".to_string())
+            .throttle(ThrottlePolicy::default().max_delay_millis(10).clone())
+            .clone();
+
+        let items_count = scraper.scrape(&client, &scraper_config).count().await;
+
+        assert_eq!(items_count, 10);
+        assert_eq!(scraper.calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn should_fail_scrape_when_rate_limited_too_often() {
+        let client = Client::new();
+        let scraper = ThrottledTestScraper {
+            calls: AtomicU32::new(0),
+        };
+        let scraper_config = ScraperConfig::new("https://foo.bar".to_string())
+            .throttle(
+                ThrottlePolicy::default()
+                    .max_delay_millis(10)
+                    .max_retries_per_page(1)
+                    .clone(),
+            )
+            .clone();
+
+        let results = scraper
+            .scrape(&client, &scraper_config)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(ScrapeError::RateLimited { .. })));
+    }
+
+    struct RequestingTestScraper {}
+
+    #[async_trait]
+    impl Scraper for RequestingTestScraper {
+        async fn scrape_page(
+            &self,
+            page_num: i16,
+            client: &ScrapeClient,
+        ) -> Result<Vec<ItemData>, ScrapeError> {
+            // nothing listens on the discard port, only the rate limit takes time
+            let _ = client.get("http://127.0.0.4:9/list").send().await;
+            match page_num {
+                1 | 2 => Ok(ItemData::generate_many(5)),
+                _ => Ok(vec![]),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_respect_host_rate_limit_for_scrape() {
+        let client = Client::new();
+        let scraper_config = ScraperConfig::new("http://127.0.0.4".to_string())
+            .rate_limit(RateLimit::new(20.0, 1))
+            .clone();
+        let start = Instant::now();
+
+        let items_count = RequestingTestScraper {}
+            .scrape(&client, &scraper_config)
+            .count()
+            .await;
+
+        // 3 requests with a burst of 1 at 20 requests/s
+        assert_eq!(items_count, 10);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    async fn should_turn_throttling_status_errors_into_rate_limited() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for status in ["429 Too Many Requests", "503 Service Unavailable"] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let _ = stream.read(&mut [0; 1024]).await;
+                let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        let client = Client::new();
+
+        let mut statuses = vec![];
+        for _ in 0..2 {
+            let err = client.get(&url).send().await.unwrap().error_for_status();
+            statuses.push(ScrapeError::from(err.unwrap_err()));
+        }
+
+        assert!(matches!(
+            statuses[0],
+            ScrapeError::RateLimited {
+                status: 429,
+                retry_after: None,
+                ..
+            }
+        ));
+        assert!(matches!(
+            statuses[1],
+            ScrapeError::RateLimited {
+                status: 503,
+                retry_after: None,
+                ..
+            }
+        ));
+    }
 }
@@ -1,6 +1,19 @@
+use crate::change_detection::ItemField;
+use crate::checkpoint::Checkpoint;
+use crate::circuit_breaker::CircuitBreaker;
+use crate::json_api_scraper::JsonApiScraperConfig;
+use crate::rate_limiter::RateLimit;
+use crate::retry::{PushRetryPolicy, RetryPolicy};
+use crate::robots::RobotsPolicy;
+use crate::selector_scraper::SelectorScraperConfig;
+use crate::throttle::ThrottlePolicy;
+use item_core::item_state::ItemState;
 use item_core::language::Language;
 use item_core::price::Currency;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct ScraperConfig {
@@ -26,8 +39,144 @@ pub struct ScraperConfig {
         default
     )]
     pub sleep_between_pages_millis: Option<u64>,
+
+    #[serde(rename = "maxPages", skip_serializing_if = "Option::is_none", default)]
+    pub max_pages: Option<usize>,
+
+    #[serde(
+        rename = "enrichConcurrency",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub enrich_concurrency: Option<usize>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub retry: Option<RetryPolicy>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub throttle: Option<ThrottlePolicy>,
+
+    #[serde(rename = "rateLimit", skip_serializing_if = "Option::is_none", default)]
+    pub rate_limit: Option<RateLimit>,
+
+    #[serde(rename = "robotsTxt", skip_serializing_if = "Option::is_none", default)]
+    pub robots_txt: Option<RobotsPolicy>,
+
+    #[serde(
+        rename = "removedItemState",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub removed_item_state: Option<ItemState>,
+
+    /// More items vanishing at once rather hints at a broken scraper than a sold out shop.
+    #[serde(
+        rename = "maxRemovalRatio",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub max_removal_ratio: Option<f64>,
+
+    /// Hash stores reading item events can only compare state and price.
+    #[serde(
+        rename = "changeDetectionFields",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub change_detection_fields: Option<Vec<ItemField>>,
+
+    #[serde(rename = "pushRetry", skip_serializing_if = "Option::is_none", default)]
+    pub push_retry: Option<PushRetryPolicy>,
+
+    #[serde(
+        rename = "circuitBreaker",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub circuit_breaker: Option<CircuitBreaker>,
+
+    #[serde(
+        rename = "maxPushLossRatio",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub max_push_loss_ratio: Option<f64>,
+
+    #[serde(rename = "dryRun", skip_serializing_if = "Option::is_none", default)]
+    pub dry_run: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub checkpoint: Option<Checkpoint>,
+
+    #[serde(
+        rename = "checkpointMarginMillis",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub checkpoint_margin_millis: Option<u64>,
+
+    /// Availability labels of this shop, taking precedence over the built-in ones.
+    #[serde(
+        rename = "stateLabels",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub state_labels: Option<HashMap<String, ItemState>>,
+
+    #[serde(
+        rename = "selectorScraper",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub selector_scraper: Option<SelectorScraperConfig>,
+
+    #[serde(
+        rename = "jsonApiScraper",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub json_api_scraper: Option<JsonApiScraperConfig>,
 }
 
+#[derive(Debug)]
+pub enum ScraperConfigError {
+    Missing { field: &'static str },
+    InvalidSelector { selector: String, reason: String },
+    InvalidPointer { pointer: String },
+    InvalidUrl { url: String, reason: String },
+    InvalidCheckpoint { next_page: String },
+}
+
+impl Display for ScraperConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScraperConfigError::Missing { field } => write!(f, "Missing '{}'", field),
+            ScraperConfigError::InvalidSelector { selector, reason } => {
+                write!(f, "Invalid selector '{}': {}", selector, reason)
+            }
+            ScraperConfigError::InvalidPointer { pointer } => {
+                write!(
+                    f,
+                    "Invalid JSON pointer '{}', has to be empty or start with '/'",
+                    pointer
+                )
+            }
+            ScraperConfigError::InvalidUrl { url, reason } => {
+                write!(f, "Invalid URL '{}': {}", url, reason)
+            }
+            ScraperConfigError::InvalidCheckpoint { next_page } => {
+                write!(
+                    f,
+                    "Checkpoint page '{}' doesn't fit the pagination",
+                    next_page
+                )
+            }
+        }
+    }
+}
+
+impl Error for ScraperConfigError {}
+
 impl ScraperConfig {
     pub fn new(base_url: String) -> Self {
         ScraperConfig {
@@ -36,6 +185,24 @@ impl ScraperConfig {
             language: None,
             shop_dimension: None,
             sleep_between_pages_millis: None,
+            max_pages: None,
+            enrich_concurrency: None,
+            retry: None,
+            throttle: None,
+            rate_limit: None,
+            robots_txt: None,
+            removed_item_state: None,
+            max_removal_ratio: None,
+            change_detection_fields: None,
+            push_retry: None,
+            circuit_breaker: None,
+            max_push_loss_ratio: None,
+            dry_run: None,
+            checkpoint: None,
+            checkpoint_margin_millis: None,
+            state_labels: None,
+            selector_scraper: None,
+            json_api_scraper: None,
         }
     }
 
@@ -66,5 +233,98 @@ impl ScraperConfig {
         self
     }
 
+    pub fn max_pages(&mut self, max_pages: usize) -> &mut Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    pub fn enrich_concurrency(&mut self, enrich_concurrency: usize) -> &mut Self {
+        self.enrich_concurrency = Some(enrich_concurrency);
+        self
+    }
+
+    pub fn retry(&mut self, retry: RetryPolicy) -> &mut Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    pub fn throttle(&mut self, throttle: ThrottlePolicy) -> &mut Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    pub fn rate_limit(&mut self, rate_limit: RateLimit) -> &mut Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    pub fn robots_txt(&mut self, robots_txt: RobotsPolicy) -> &mut Self {
+        self.robots_txt = Some(robots_txt);
+        self
+    }
+
+    pub fn removed_item_state(&mut self, removed_item_state: ItemState) -> &mut Self {
+        self.removed_item_state = Some(removed_item_state);
+        self
+    }
+
+    pub fn max_removal_ratio(&mut self, max_removal_ratio: f64) -> &mut Self {
+        self.max_removal_ratio = Some(max_removal_ratio);
+        self
+    }
+
+    pub fn change_detection_fields(
+        &mut self,
+        change_detection_fields: Vec<ItemField>,
+    ) -> &mut Self {
+        self.change_detection_fields = Some(change_detection_fields);
+        self
+    }
+
+    pub fn push_retry(&mut self, push_retry: PushRetryPolicy) -> &mut Self {
+        self.push_retry = Some(push_retry);
+        self
+    }
+
+    pub fn circuit_breaker(&mut self, circuit_breaker: CircuitBreaker) -> &mut Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    pub fn max_push_loss_ratio(&mut self, max_push_loss_ratio: f64) -> &mut Self {
+        self.max_push_loss_ratio = Some(max_push_loss_ratio);
+        self
+    }
+
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.dry_run = Some(dry_run);
+        self
+    }
+
+    pub fn checkpoint(&mut self, checkpoint: Checkpoint) -> &mut Self {
+        self.checkpoint = Some(checkpoint);
+        self
+    }
+
+    pub fn checkpoint_margin_millis(&mut self, checkpoint_margin_millis: u64) -> &mut Self {
+        self.checkpoint_margin_millis = Some(checkpoint_margin_millis);
+        self
+    }
+
+    pub fn state_labels(&mut self, state_labels: HashMap<String, ItemState>) -> &mut Self {
+        self.state_labels = Some(state_labels);
+        self
+    }
+
+    pub fn selector_scraper(&mut self, selector_scraper: SelectorScraperConfig) -> &mut Self {
+        self.selector_scraper = Some(selector_scraper);
+        self
+    }
+
+    pub fn json_api_scraper(&mut self, json_api_scraper: JsonApiScraperConfig) -> &mut Self {
+        self.json_api_scraper = Some(json_api_scraper);
+        self
+    }
+
     // endregion
 }
@@ -0,0 +1,567 @@
+use crate::pagination::{Page, PageToken};
+use crate::price_parser::parse_price;
+use crate::scrape_client::ScrapeClient;
+use crate::scraper::{ScrapeError, Scraper};
+use crate::scraper_config::{ScraperConfig, ScraperConfigError};
+use crate::state_classifier::StateClassifier;
+use ::scraper::{ElementRef, Html, Selector};
+use async_trait::async_trait;
+use item_core::item_data::ItemData;
+use item_core::item_state::ItemState;
+use item_core::language::Language;
+use item_core::price::Currency;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SelectorPagination {
+    Numbered {
+        #[serde(rename = "urlTemplate")]
+        url_template: String,
+
+        #[serde(rename = "firstPage", default = "first_page_number")]
+        first_page: i16,
+    },
+    NextLink {
+        #[serde(rename = "firstUrl")]
+        first_url: String,
+
+        #[serde(rename = "nextSelector")]
+        next_selector: String,
+    },
+}
+
+fn first_page_number() -> i16 {
+    1
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Transform {
+    Trim,
+    Lowercase,
+    Uppercase,
+    Prefix { value: String },
+    Suffix { value: String },
+    Replace { from: String, to: String },
+    AbsoluteUrl,
+}
+
+impl Transform {
+    pub fn apply(&self, value: String, page_url: &Url) -> String {
+        match self {
+            Transform::Trim => value.trim().to_string(),
+            Transform::Lowercase => value.to_lowercase(),
+            Transform::Uppercase => value.to_uppercase(),
+            Transform::Prefix { value: prefix } => format!("{}{}", prefix, value),
+            Transform::Suffix { value: suffix } => format!("{}{}", value, suffix),
+            Transform::Replace { from, to } => value.replace(from, to),
+            Transform::AbsoluteUrl => page_url
+                .join(&value)
+                .map(|url| url.to_string())
+                .unwrap_or(value),
+        }
+    }
+}
+
+/// Without `selector` the item's element itself is used, without `attribute` its text,
+/// with whitespace collapsed.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct FieldSelector {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub selector: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub attribute: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub transforms: Vec<Transform>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct SelectorFields {
+    #[serde(rename = "itemId")]
+    pub item_id: FieldSelector,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub url: Option<FieldSelector>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub name: Option<FieldSelector>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub description: Option<FieldSelector>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub price: Option<FieldSelector>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub state: Option<FieldSelector>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub category: Option<FieldSelector>,
+
+    #[serde(rename = "imageUrl", skip_serializing_if = "Option::is_none", default)]
+    pub image_url: Option<FieldSelector>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct SelectorScraperConfig {
+    pub pagination: SelectorPagination,
+
+    #[serde(rename = "itemSelector")]
+    pub item_selector: String,
+
+    pub fields: SelectorFields,
+
+    /// Takes precedence over `stateLabels` and the built-in labels.
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub states: HashMap<String, ItemState>,
+
+    #[serde(
+        rename = "defaultState",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub default_state: Option<ItemState>,
+}
+
+#[derive(Debug)]
+struct CompiledField {
+    selector: Option<Selector>,
+    attribute: Option<String>,
+    transforms: Vec<Transform>,
+}
+
+impl CompiledField {
+    fn compile(field: &FieldSelector) -> Result<Self, ScraperConfigError> {
+        Ok(CompiledField {
+            selector: field
+                .selector
+                .as_deref()
+                .map(compile_selector)
+                .transpose()?,
+            attribute: field.attribute.clone(),
+            transforms: field.transforms.clone(),
+        })
+    }
+
+    fn extract(&self, element: ElementRef, page_url: &Url) -> Option<String> {
+        let target = match &self.selector {
+            Some(selector) => element.select(selector).next()?,
+            None => element,
+        };
+        let raw = match &self.attribute {
+            Some(attribute) => target.value().attr(attribute)?.to_string(),
+            None => target.text().collect::<Vec<_>>().join(" "),
+        };
+        let value = self.transforms.iter().fold(
+            raw.split_whitespace().collect::<Vec<_>>().join(" "),
+            |value, transform| transform.apply(value, page_url),
+        );
+        Some(value).filter(|value| !value.is_empty())
+    }
+}
+
+fn compile_selector(selector: &str) -> Result<Selector, ScraperConfigError> {
+    Selector::parse(selector).map_err(|e| ScraperConfigError::InvalidSelector {
+        selector: selector.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+#[derive(Debug)]
+pub struct SelectorScraper {
+    base_url: String,
+    pagination: SelectorPagination,
+    next_selector: Option<Selector>,
+    item_selector: Selector,
+    item_id: CompiledField,
+    url: Option<CompiledField>,
+    name: Option<CompiledField>,
+    description: Option<CompiledField>,
+    price: Option<CompiledField>,
+    state: Option<CompiledField>,
+    category: Option<CompiledField>,
+    image_url: Option<CompiledField>,
+    states: StateClassifier,
+    default_state: Option<ItemState>,
+    currency: Option<Currency>,
+    language: Option<Language>,
+}
+
+impl TryFrom<ScraperConfig> for SelectorScraper {
+    type Error = ScraperConfigError;
+
+    fn try_from(scraper_config: ScraperConfig) -> Result<Self, Self::Error> {
+        let mut states = StateClassifier::for_config(&scraper_config);
+        let config = scraper_config
+            .selector_scraper
+            .ok_or(ScraperConfigError::Missing {
+                field: "selectorScraper",
+            })?;
+        let fields = &config.fields;
+        let compile =
+            |field: &Option<FieldSelector>| field.as_ref().map(CompiledField::compile).transpose();
+
+        if (fields.name.is_some() || fields.description.is_some())
+            && scraper_config.language.is_none()
+        {
+            return Err(ScraperConfigError::Missing { field: "language" });
+        }
+        if let Some(checkpoint) = &scraper_config.checkpoint
+            && !matches!(
+                (&config.pagination, &checkpoint.next_page),
+                (
+                    SelectorPagination::Numbered { .. },
+                    PageToken::Number { .. }
+                ) | (
+                    SelectorPagination::NextLink { .. },
+                    PageToken::NextLink { .. }
+                )
+            )
+        {
+            return Err(ScraperConfigError::InvalidCheckpoint {
+                next_page: checkpoint.next_page.to_string(),
+            });
+        }
+        states.overrides(&config.states);
+
+        Ok(SelectorScraper {
+            base_url: scraper_config.base_url,
+            next_selector: match &config.pagination {
+                SelectorPagination::NextLink { next_selector, .. } => {
+                    Some(compile_selector(next_selector)?)
+                }
+                SelectorPagination::Numbered { .. } => None,
+            },
+            item_selector: compile_selector(&config.item_selector)?,
+            item_id: CompiledField::compile(&fields.item_id)?,
+            url: compile(&fields.url)?,
+            name: compile(&fields.name)?,
+            description: compile(&fields.description)?,
+            price: compile(&fields.price)?,
+            state: compile(&fields.state)?,
+            category: compile(&fields.category)?,
+            image_url: compile(&fields.image_url)?,
+            pagination: config.pagination,
+            states,
+            default_state: config.default_state,
+            currency: scraper_config.currency,
+            language: scraper_config.language,
+        })
+    }
+}
+
+impl SelectorScraper {
+    pub fn parse_page(
+        &self,
+        html: &str,
+        page_url: &Url,
+    ) -> (Vec<Result<ItemData, ScrapeError>>, Option<String>) {
+        let document = Html::parse_document(html);
+        let items = document
+            .select(&self.item_selector)
+            .map(|element| self.parse_item(element, page_url))
+            .collect();
+        let next_url = self.next_selector.as_ref().and_then(|selector| {
+            document
+                .select(selector)
+                .next()
+                .and_then(|element| element.value().attr("href"))
+                .and_then(|href| page_url.join(href).ok())
+                .map(|url| url.to_string())
+        });
+        (items, next_url)
+    }
+
+    fn parse_item(&self, element: ElementRef, page_url: &Url) -> Result<ItemData, ScrapeError> {
+        let extract = |field: &Option<CompiledField>| {
+            field
+                .as_ref()
+                .and_then(|field| field.extract(element, page_url))
+        };
+        let id =
+            self.item_id
+                .extract(element, page_url)
+                .ok_or_else(|| ScrapeError::InvalidItem {
+                    item_id: page_url.to_string(),
+                    reason: "missing id".to_string(),
+                })?;
+
+        let mut item = ItemData::new(format!("{}#{}", self.base_url, id))
+            .source_id(self.base_url.clone())
+            .to_owned();
+        if let Some(url) = extract(&self.url) {
+            item.url(url);
+        }
+        let state = extract(&self.state)
+            .and_then(|text| self.states.classify(&text))
+            .or_else(|| self.default_state.clone());
+        if let Some(state) = state {
+            item.state(state);
+        }
+        if let Some(text) = extract(&self.price) {
+            let price = parse_price(&text, self.currency.as_ref(), self.language.as_ref())
+                .map_err(|e| ScrapeError::InvalidItem {
+                    item_id: item.item_id.clone(),
+                    reason: e.to_string(),
+                })?;
+            item.price(price);
+        }
+        if let Some(language) = &self.language {
+            if let Some(name) = extract(&self.name) {
+                item.name.insert(language.clone(), name);
+            }
+            if let Some(description) = extract(&self.description) {
+                item.description.insert(language.clone(), description);
+            }
+        }
+        item.category = extract(&self.category);
+        item.image_url = extract(&self.image_url);
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl Scraper for SelectorScraper {
+    fn first_page(&self) -> PageToken {
+        match &self.pagination {
+            SelectorPagination::Numbered { first_page, .. } => {
+                PageToken::Number { page: *first_page }
+            }
+            SelectorPagination::NextLink { first_url, .. } => {
+                PageToken::first_link(first_url.clone())
+            }
+        }
+    }
+
+    fn page_url(&self, token: &PageToken) -> Option<String> {
+        match (&self.pagination, token) {
+            (SelectorPagination::Numbered { url_template, .. }, PageToken::Number { page }) => {
+                Some(url_template.replace("{page}", &page.to_string()))
+            }
+            (_, PageToken::NextLink { url }) => Some(url.clone()),
+            _ => None,
+        }
+    }
+
+    async fn fetch_page(
+        &self,
+        token: &PageToken,
+        client: &ScrapeClient,
+    ) -> Result<Page, ScrapeError> {
+        let url = self.page_url(token).ok_or_else(|| {
+            ScrapeError::custom(format!("Page {} doesn't fit the pagination", token))
+        })?;
+        let response = client.get(&url).send().await?.error_for_status()?;
+        let page_url = response.url().clone();
+        let html = response.text().await?;
+        let (results, next_url) = self.parse_page(&html, &page_url);
+
+        // pages of only invalid items still count as non-empty
+        let next = match token {
+            PageToken::Number { page } if !results.is_empty() => {
+                Some(PageToken::Number { page: page + 1 })
+            }
+            PageToken::Number { .. } => None,
+            _ => next_url
+                .filter(|url| !url.is_empty())
+                .map(|url| PageToken::NextLink { url }),
+        };
+        Ok(Page::from_results(results, next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checkpoint::Checkpoint;
+    use crate::pagination::PageToken;
+    use crate::report::ScrapeReport;
+    use crate::scraper::{ScrapeError, Scraper};
+    use crate::scraper_config::{ScraperConfig, ScraperConfigError};
+    use crate::selector_scraper::{SelectorScraper, SelectorScraperConfig};
+    use item_core::item_state::ItemState::{AVAILABLE, RESERVED, SOLD};
+    use item_core::language::Language::DE;
+    use item_core::price::Currency::EUR;
+    use item_core::price::Price;
+    use reqwest::Url;
+
+    const HTML: &str = r#"
+        <html><body>
+            <div class="product" data-id="1">
+                <a href="/p/1"><h2>  Pickelhaube
+                    M1895 </h2></a>
+                <span class="price">1.299,00 €</span>
+                <span class="badge">Verkauft</span>
+            </div>
+            <div class="product" data-id="2">
+                <a href="/p/2"><h2>Koppelschloss</h2></a>
+                <span class="price">85,50 €</span>
+            </div>
+            <div class="product">
+                <h2>Without id</h2>
+            </div>
+            <a class="next" href="?page=2">Weiter</a>
+        </body></html>
+    "#;
+
+    fn make_config(pagination: &str) -> ScraperConfig {
+        let selector_config: SelectorScraperConfig = serde_json::from_str(&format!(
+            r#"{{
+                "pagination": {},
+                "itemSelector": "div.product",
+                "fields": {{
+                    "itemId": {{ "attribute": "data-id" }},
+                    "url": {{ "selector": "a", "attribute": "href", "transforms": [{{ "type": "absoluteUrl" }}] }},
+                    "name": {{ "selector": "h2" }},
+                    "price": {{ "selector": ".price" }},
+                    "state": {{ "selector": ".badge", "transforms": [{{ "type": "lowercase" }}] }}
+                }},
+                "states": {{ "verkauft": "SOLD" }},
+                "defaultState": "AVAILABLE"
+            }}"#,
+            pagination
+        ))
+        .unwrap();
+        ScraperConfig::new("https://foo.bar".to_string())
+            .currency(EUR)
+            .language(DE)
+            .selector_scraper(selector_config)
+            .clone()
+    }
+
+    fn make_scraper() -> SelectorScraper {
+        make_config(
+            r#"{ "type": "nextLink", "firstUrl": "https://foo.bar/shop", "nextSelector": "a.next" }"#,
+        )
+        .try_into()
+        .unwrap()
+    }
+
+    #[test]
+    fn should_extract_items_by_selectors() {
+        let page_url = Url::parse("https://foo.bar/shop").unwrap();
+
+        let (results, next_url) = make_scraper().parse_page(HTML, &page_url);
+        let items = results.into_iter().flatten().collect::<Vec<_>>();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].item_id, "https://foo.bar#1");
+        assert_eq!(items[0].source_id, Some("https://foo.bar".to_string()));
+        assert_eq!(items[0].url, Some("https://foo.bar/p/1".to_string()));
+        assert_eq!(
+            items[0].name.get(&DE),
+            Some(&"Pickelhaube M1895".to_string())
+        );
+        assert_eq!(items[0].price, Some(Price::new(EUR, 1299f32)));
+        assert_eq!(items[0].state, Some(SOLD));
+        assert_eq!(items[1].price, Some(Price::new(EUR, 85.5f32)));
+        assert_eq!(items[1].state, Some(AVAILABLE));
+        assert_eq!(next_url, Some("https://foo.bar/shop?page=2".to_string()));
+    }
+
+    #[test]
+    fn should_classify_unmapped_state_labels() {
+        let page_url = Url::parse("https://foo.bar/shop").unwrap();
+        let html = r#"
+            <div class="product" data-id="1"><span class="badge">Reserviert</span></div>
+            <div class="product" data-id="2"><span class="badge">Nicht mehr verfügbar</span></div>
+            <div class="product" data-id="3"><span class="badge">Neu!</span></div>
+        "#;
+
+        let (results, _) = make_scraper().parse_page(html, &page_url);
+        let items = results.into_iter().flatten().collect::<Vec<_>>();
+
+        assert_eq!(items[0].state, Some(RESERVED));
+        assert_eq!(items[1].state, Some(SOLD));
+        assert_eq!(items[2].state, Some(AVAILABLE));
+    }
+
+    #[test]
+    fn should_report_items_without_id_or_with_unparseable_price() {
+        let page_url = Url::parse("https://foo.bar/shop").unwrap();
+        let html = r#"
+            <div class="product"><h2>Without id</h2></div>
+            <div class="product" data-id="2"><span class="price">auf Anfrage</span></div>
+        "#;
+
+        let (results, _) = make_scraper().parse_page(html, &page_url);
+
+        assert!(matches!(
+            &results[0],
+            Err(ScrapeError::InvalidItem { item_id, reason })
+                if item_id == "https://foo.bar/shop" && reason == "missing id"
+        ));
+        assert!(matches!(
+            &results[1],
+            Err(ScrapeError::InvalidItem { item_id, .. }) if item_id == "https://foo.bar#2"
+        ));
+    }
+
+    #[test]
+    fn should_build_page_urls_from_template() {
+        let scraper: SelectorScraper = make_config(
+            r#"{ "type": "numbered", "urlTemplate": "https://foo.bar/shop?page={page}" }"#,
+        )
+        .try_into()
+        .unwrap();
+
+        assert_eq!(scraper.first_page(), PageToken::Number { page: 1 });
+        assert_eq!(
+            scraper.page_url(&PageToken::Number { page: 3 }),
+            Some("https://foo.bar/shop?page=3".to_string())
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_selectors() {
+        let mut config = make_config(
+            r#"{ "type": "numbered", "urlTemplate": "https://foo.bar/shop?page={page}" }"#,
+        );
+        if let Some(selector_config) = config.selector_scraper.as_mut() {
+            selector_config.item_selector = "div[".to_string();
+        }
+
+        let actual = SelectorScraper::try_from(config);
+
+        assert!(matches!(
+            actual,
+            Err(ScraperConfigError::InvalidSelector { .. })
+        ));
+    }
+
+    #[test]
+    fn should_reject_checkpoint_of_other_pagination() {
+        let config = make_config(
+            r#"{ "type": "numbered", "urlTemplate": "https://foo.bar/shop?page={page}" }"#,
+        )
+        .checkpoint(Checkpoint::next(
+            None,
+            PageToken::first_link("https://foo.bar/shop".to_string()),
+            &ScrapeReport::default(),
+        ))
+        .clone();
+
+        let actual = SelectorScraper::try_from(config);
+
+        assert!(matches!(
+            actual,
+            Err(ScraperConfigError::InvalidCheckpoint { .. })
+        ));
+    }
+
+    #[test]
+    fn should_require_selector_scraper_config() {
+        let actual = SelectorScraper::try_from(ScraperConfig::new("https://foo.bar".to_string()));
+
+        assert!(matches!(
+            actual,
+            Err(ScraperConfigError::Missing {
+                field: "selectorScraper"
+            })
+        ));
+    }
+}
@@ -0,0 +1,223 @@
+use crate::change_detection::ItemDiff;
+use crate::report::{PushFailure, PushResult};
+use crate::scraper_config::ScraperConfig;
+use async_trait::async_trait;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tracing::error;
+
+/// Implementations report every diff either as pushed or as a [`PushFailure`].
+#[async_trait]
+pub trait ItemSink: Send + Sync {
+    async fn push(&self, diffs: Vec<ItemDiff>, scraper_config: &ScraperConfig) -> PushResult;
+}
+
+fn fail_all(diffs: Vec<ItemDiff>, reason: &str) -> PushResult {
+    PushResult {
+        pushed: 0,
+        failures: diffs
+            .into_iter()
+            .map(|diff| PushFailure::new(diff.item.item_id, reason.to_string()))
+            .collect(),
+    }
+}
+
+fn to_json_lines(diffs: Vec<ItemDiff>, push_result: &mut PushResult) -> (String, usize) {
+    let mut lines = String::new();
+    let mut count = 0;
+    for diff in diffs {
+        match serde_json::to_string(&diff) {
+            Ok(line) => {
+                lines.push_str(&line);
+                lines.push('\n');
+                count += 1;
+            }
+            Err(e) => {
+                error!(error = %e, body = ?diff.item, "Serializing ItemDiff failed.");
+                push_result
+                    .failures
+                    .push(PushFailure::new(diff.item.item_id, e.to_string()));
+            }
+        }
+    }
+    (lines, count)
+}
+
+#[derive(Debug, Default)]
+pub struct StdoutSink;
+
+#[async_trait]
+impl ItemSink for StdoutSink {
+    async fn push(&self, diffs: Vec<ItemDiff>, _: &ScraperConfig) -> PushResult {
+        let mut push_result = PushResult::default();
+        let (lines, count) = to_json_lines(diffs, &mut push_result);
+        // A single write, so lines of concurrent batches don't interleave
+        print!("{}", lines);
+        push_result.pushed = count;
+        push_result
+    }
+}
+
+#[derive(Debug)]
+pub struct JsonLinesFileSink {
+    file: tokio::sync::Mutex<File>,
+}
+
+impl JsonLinesFileSink {
+    pub async fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(JsonLinesFileSink {
+            file: tokio::sync::Mutex::new(File::create(path).await?),
+        })
+    }
+}
+
+#[async_trait]
+impl ItemSink for JsonLinesFileSink {
+    async fn push(&self, diffs: Vec<ItemDiff>, _: &ScraperConfig) -> PushResult {
+        let mut push_result = PushResult::default();
+        let item_ids = diffs
+            .iter()
+            .map(|diff| diff.item.item_id.clone())
+            .collect::<Vec<_>>();
+        let (lines, count) = to_json_lines(diffs, &mut push_result);
+
+        let mut file = self.file.lock().await;
+        let written = match file.write_all(lines.as_bytes()).await {
+            Ok(()) => file.flush().await,
+            Err(e) => Err(e),
+        };
+        match written {
+            Ok(()) => push_result.pushed = count,
+            Err(e) => {
+                error!(error = %e, "Writing JSON lines failed.");
+                let failed = push_result
+                    .failures
+                    .iter()
+                    .map(|failure| failure.item_id.clone())
+                    .collect::<Vec<_>>();
+                push_result.failures.extend(
+                    item_ids
+                        .into_iter()
+                        .filter(|item_id| !failed.contains(item_id))
+                        .map(|item_id| PushFailure::new(item_id, e.to_string())),
+                );
+            }
+        }
+        push_result
+    }
+}
+
+/// A batch is either accepted as a whole by a successful status code or fails as a whole.
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookSink {
+    pub fn new(client: reqwest::Client, url: String) -> Self {
+        WebhookSink { client, url }
+    }
+}
+
+#[async_trait]
+impl ItemSink for WebhookSink {
+    async fn push(&self, diffs: Vec<ItemDiff>, _: &ScraperConfig) -> PushResult {
+        let response = self
+            .client
+            .post(&self.url)
+            .json(&diffs)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match response {
+            Ok(_) => PushResult {
+                pushed: diffs.len(),
+                failures: vec![],
+            },
+            Err(e) => {
+                error!(error = %e, url = %self.url, "Posting diffs to webhook failed.");
+                fail_all(diffs, &e.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InMemorySink {
+    diffs: std::sync::Mutex<Vec<ItemDiff>>,
+}
+
+impl InMemorySink {
+    pub fn new() -> Self {
+        InMemorySink::default()
+    }
+
+    pub fn diffs(&self) -> Vec<ItemDiff> {
+        self.diffs.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ItemSink for InMemorySink {
+    async fn push(&self, diffs: Vec<ItemDiff>, _: &ScraperConfig) -> PushResult {
+        let pushed = diffs.len();
+        self.diffs.lock().unwrap().extend(diffs);
+        PushResult {
+            pushed,
+            failures: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::change_detection::ItemDiff;
+    use crate::scraper_config::ScraperConfig;
+    use crate::sink::{InMemorySink, ItemSink, JsonLinesFileSink};
+    use item_core::item_data::ItemData;
+    use test_api::generator::Generator;
+
+    #[tokio::test]
+    async fn should_collect_diffs_in_memory() {
+        let sink = InMemorySink::new();
+        let diffs = ItemData::generate_many(3)
+            .into_iter()
+            .map(ItemDiff::created)
+            .collect::<Vec<_>>();
+
+        let push_result = sink
+            .push(
+                diffs.clone(),
+                &ScraperConfig::new("https://foo.bar".to_string()),
+            )
+            .await;
+
+        assert_eq!(push_result.pushed, 3);
+        assert!(push_result.failures.is_empty());
+        assert_eq!(sink.diffs(), diffs);
+    }
+
+    #[tokio::test]
+    async fn should_write_one_json_line_per_diff() {
+        let path = std::env::temp_dir().join(format!("sink-test-{}.jsonl", uuid::Uuid::new_v4()));
+        let sink = JsonLinesFileSink::create(&path).await.unwrap();
+        let diffs = ItemData::generate_many(2)
+            .into_iter()
+            .map(ItemDiff::created)
+            .collect::<Vec<_>>();
+
+        let push_result = sink
+            .push(diffs, &ScraperConfig::new("https://foo.bar".to_string()))
+            .await;
+
+        let written = tokio::fs::read_to_string(&path).await.unwrap();
+        let _ = tokio::fs::remove_file(&path).await;
+        assert_eq!(push_result.pushed, 2);
+        assert_eq!(written.lines().count(), 2);
+        assert!(written.lines().all(
+            |line| serde_json::from_str::<serde_json::Value>(line).unwrap()["created"] == true
+        ));
+    }
+}
@@ -0,0 +1,284 @@
+use crate::batcher::{MAX_SQS_BATCH_PAYLOAD_BYTES, entry_size, pack_entries};
+use crate::change_detection::ItemDiff;
+use crate::report::{PushFailure, PushResult};
+use crate::retry::PushRetryPolicy;
+use crate::scraper_config::ScraperConfig;
+use crate::sink::ItemSink;
+use async_trait::async_trait;
+use aws_sdk_sqs::config::http::HttpResponse;
+use aws_sdk_sqs::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_sqs::operation::send_message_batch::SendMessageBatchError;
+use aws_sdk_sqs::types::{MessageAttributeValue, SendMessageBatchRequestEntry};
+use std::collections::HashMap;
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+pub struct SqsSink {
+    sqs_client: aws_sdk_sqs::Client,
+    queue_url: String,
+}
+
+impl SqsSink {
+    pub fn new(sqs_client: aws_sdk_sqs::Client, queue_url: String) -> Self {
+        SqsSink {
+            sqs_client,
+            queue_url,
+        }
+    }
+}
+
+#[async_trait]
+impl ItemSink for SqsSink {
+    async fn push(&self, diffs: Vec<ItemDiff>, scraper_config: &ScraperConfig) -> PushResult {
+        let retry_policy = scraper_config.push_retry.clone().unwrap_or_default();
+        push_diffs(diffs, &self.sqs_client, &self.queue_url, &retry_policy).await
+    }
+}
+
+async fn push_diffs(
+    diffs: Vec<ItemDiff>,
+    sqs_client: &aws_sdk_sqs::Client,
+    item_write_lambda_q_url: &str,
+    retry_policy: &PushRetryPolicy,
+) -> PushResult {
+    let mut push_result = PushResult::default();
+    let mut entry_item_ids = HashMap::new();
+    let msg_entries = diffs
+        .into_iter()
+        .filter_map(|diff| match serde_json::to_string(&diff.item) {
+            Ok(body) => {
+                let id = Uuid::new_v4().to_string();
+                let entry = SendMessageBatchRequestEntry::builder()
+                    .message_body(body)
+                    .id(id.clone())
+                    .set_message_attributes(Some(change_message_attributes(&diff)))
+                    .build()
+                    .expect("shouldn't fail because 'id' and 'message_body' have been set");
+                entry_item_ids.insert(id, diff.item.item_id);
+                Some(entry)
+            }
+            Err(e) => {
+                error!(
+                    error = %e,
+                    body = ?diff.item,
+                    "Serializing ItemData failed.",
+                );
+                push_result
+                    .failures
+                    .push(PushFailure::new(diff.item.item_id, e.to_string()));
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let (batches, oversized) = pack_entries(msg_entries);
+    for entry in oversized {
+        let item_id = entry_item_ids
+            .get(entry.id())
+            .cloned()
+            .unwrap_or_else(|| entry.id().to_string());
+        error!(
+            itemId = %item_id,
+            size = entry_size(&entry),
+            "Message exceeds the SQS payload limit."
+        );
+        push_result.failures.push(PushFailure::new(
+            item_id,
+            format!(
+                "Message of {} bytes exceeds the SQS payload limit of {} bytes",
+                entry_size(&entry),
+                MAX_SQS_BATCH_PAYLOAD_BYTES
+            ),
+        ));
+    }
+    for batch in batches {
+        send_batch(
+            batch,
+            sqs_client,
+            item_write_lambda_q_url,
+            retry_policy,
+            &entry_item_ids,
+            &mut push_result,
+        )
+        .await;
+    }
+
+    if !push_result.failures.is_empty() {
+        error!(
+            failed = push_result.failures.len(),
+            failures = ?push_result.failures,
+            "Giving up on batch entries."
+        );
+    }
+
+    push_result
+}
+
+async fn send_batch(
+    batch: Vec<SendMessageBatchRequestEntry>,
+    sqs_client: &aws_sdk_sqs::Client,
+    item_write_lambda_q_url: &str,
+    retry_policy: &PushRetryPolicy,
+    entry_item_ids: &HashMap<String, String>,
+    push_result: &mut PushResult,
+) {
+    let mut pending = batch;
+    let mut attempt = 1;
+    while !pending.is_empty() {
+        // failed entries worth retrying, by entry id with the reason they failed
+        let mut retryable = HashMap::new();
+        let batch_output_res = sqs_client
+            .send_message_batch()
+            .queue_url(item_write_lambda_q_url)
+            .set_entries(Some(pending.clone()))
+            .send()
+            .await;
+
+        match batch_output_res {
+            Ok(batch_output) => {
+                let failures = batch_output.failed;
+                let successes = batch_output.successful.len();
+                info!(
+                    successful = successes,
+                    failed = failures.len(),
+                    failures = ?failures,
+                    attempt,
+                    "Successfully sent batch."
+                );
+
+                push_result.pushed += successes;
+                for failure in failures {
+                    let reason = failure.message.unwrap_or(failure.code);
+                    if failure.sender_fault {
+                        let item_id = entry_item_ids
+                            .get(&failure.id)
+                            .cloned()
+                            .unwrap_or(failure.id);
+                        push_result.failures.push(PushFailure::new(item_id, reason));
+                    } else {
+                        retryable.insert(failure.id, reason);
+                    }
+                }
+            }
+            Err(e) if is_retryable(&e) => {
+                warn!(error = %e, attempt, "Failed message batch.");
+                retryable.extend(
+                    pending
+                        .iter()
+                        .map(|entry| (entry.id().to_string(), e.to_string())),
+                );
+            }
+            Err(e) => {
+                error!(error = %e, "Failed message batch, not retrying.");
+                push_result.failures.extend(pending.iter().map(|entry| {
+                    let item_id = entry_item_ids
+                        .get(entry.id())
+                        .cloned()
+                        .unwrap_or_else(|| entry.id().to_string());
+                    PushFailure::new(item_id, e.to_string())
+                }));
+            }
+        }
+
+        if retryable.is_empty() {
+            break;
+        }
+        if attempt >= retry_policy.max_attempts {
+            push_result
+                .failures
+                .extend(retryable.into_iter().map(|(id, reason)| {
+                    let item_id = entry_item_ids.get(&id).cloned().unwrap_or(id);
+                    PushFailure::new(item_id, reason)
+                }));
+            break;
+        }
+
+        let delay = retry_policy.backoff(attempt);
+        warn!(
+            retrying = retryable.len(),
+            attempt,
+            delayMillis = delay.as_millis() as u64,
+            "Retrying failed batch entries."
+        );
+        sleep(delay).await;
+        attempt += 1;
+        pending.retain(|entry| retryable.contains_key(entry.id()));
+    }
+}
+
+const THROTTLING_ERROR_CODES: [&str; 3] = [
+    "ThrottlingException",
+    "RequestThrottled",
+    "AWS.SimpleQueueService.RequestThrottled",
+];
+
+/// Unlike e.g. a missing queue or missing permissions, transport errors, throttling and server
+/// errors may pass.
+fn is_retryable(err: &SdkError<SendMessageBatchError, HttpResponse>) -> bool {
+    matches!(
+        err,
+        SdkError::DispatchFailure(_) | SdkError::TimeoutError(_)
+    ) || err
+        .code()
+        .is_some_and(|code| THROTTLING_ERROR_CODES.contains(&code))
+        || err
+            .raw_response()
+            .is_some_and(|response| response.status().is_server_error())
+}
+
+/// Tells consumers what changed without altering the message body, which stays plain `ItemData`.
+fn change_message_attributes(diff: &ItemDiff) -> HashMap<String, MessageAttributeValue> {
+    let mut attributes = HashMap::new();
+    let change_kinds = diff
+        .change_kinds()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    attributes.insert(
+        "changeKinds".to_string(),
+        string_message_attribute(change_kinds),
+    );
+    // SQS rejects empty attribute values
+    if !diff.changed_fields.is_empty() {
+        let changed_fields = diff
+            .changed_fields
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        attributes.insert(
+            "changedFields".to_string(),
+            string_message_attribute(changed_fields),
+        );
+    }
+    attributes
+}
+
+fn string_message_attribute(value: String) -> MessageAttributeValue {
+    MessageAttributeValue::builder()
+        .data_type("String")
+        .string_value(value)
+        .build()
+        .expect("shouldn't fail because 'data_type' has been set")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sqs_sink::is_retryable;
+    use aws_sdk_sqs::config::http::HttpResponse;
+    use aws_sdk_sqs::error::SdkError;
+    use aws_sdk_sqs::operation::send_message_batch::SendMessageBatchError;
+
+    #[test]
+    fn should_retry_timeouts_but_not_construction_failures() {
+        let timeout: SdkError<SendMessageBatchError, HttpResponse> =
+            SdkError::timeout_error("timed out");
+        let construction_failure: SdkError<SendMessageBatchError, HttpResponse> =
+            SdkError::construction_failure("invalid request");
+
+        assert!(is_retryable(&timeout));
+        assert!(!is_retryable(&construction_failure));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::scraper_config::ScraperConfig;
+use item_core::item_state::ItemState;
+use item_core::item_state::ItemState::{AVAILABLE, RESERVED, SOLD};
+use item_core::language::Language;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Labels used by shops of every language.
+const EN_LABELS: [(&str, ItemState); 14] = [
+    ("available", AVAILABLE),
+    ("in stock", AVAILABLE),
+    ("buy now", AVAILABLE),
+    ("add to cart", AVAILABLE),
+    ("sold", SOLD),
+    ("sold out", SOLD),
+    ("out of stock", SOLD),
+    ("not available", SOLD),
+    ("unavailable", SOLD),
+    ("no longer available", SOLD),
+    ("reserved", RESERVED),
+    ("on hold", RESERVED),
+    ("pending", RESERVED),
+    ("sale pending", RESERVED),
+];
+
+const DE_LABELS: [(&str, ItemState); 13] = [
+    ("verfügbar", AVAILABLE),
+    ("lieferbar", AVAILABLE),
+    ("auf lager", AVAILABLE),
+    ("vorrätig", AVAILABLE),
+    ("in den warenkorb", AVAILABLE),
+    ("verkauft", SOLD),
+    ("ausverkauft", SOLD),
+    ("nicht verfügbar", SOLD),
+    ("nicht mehr verfügbar", SOLD),
+    ("nicht lieferbar", SOLD),
+    ("vergriffen", SOLD),
+    ("reserviert", RESERVED),
+    ("angefragt", RESERVED),
+];
+
+const FR_LABELS: [(&str, ItemState); 7] = [
+    ("disponible", AVAILABLE),
+    ("en stock", AVAILABLE),
+    ("vendu", SOLD),
+    ("épuisé", SOLD),
+    ("indisponible", SOLD),
+    ("réservé", RESERVED),
+    ("en attente", RESERVED),
+];
+
+fn built_in_labels(language: &Language) -> &'static [(&'static str, ItemState)] {
+    match language {
+        Language::DE => &DE_LABELS,
+        Language::FR => &FR_LABELS,
+        _ => &[],
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A text matches a label if it is the label or contains it as whole words. Overrides win over
+/// built-in labels, otherwise the longest matching label wins, so `"nicht verfügbar"` is sold
+/// although it contains `"verfügbar"`.
+#[derive(Debug, Clone, Default)]
+pub struct StateClassifier {
+    labels: HashMap<String, ItemState>,
+    overrides: HashMap<String, ItemState>,
+}
+
+impl StateClassifier {
+    pub fn new(language: Option<&Language>) -> Self {
+        let labels = EN_LABELS
+            .iter()
+            .chain(language.map(built_in_labels).unwrap_or_default())
+            .map(|(label, state)| (normalize(label), state.clone()))
+            .collect();
+        StateClassifier {
+            labels,
+            overrides: HashMap::new(),
+        }
+    }
+
+    pub fn for_config(scraper_config: &ScraperConfig) -> Self {
+        let mut classifier = StateClassifier::new(scraper_config.language.as_ref());
+        if let Some(state_labels) = &scraper_config.state_labels {
+            classifier.overrides(state_labels);
+        }
+        classifier
+    }
+
+    pub fn overrides(&mut self, overrides: &HashMap<String, ItemState>) -> &mut Self {
+        self.overrides.extend(
+            overrides
+                .iter()
+                .map(|(label, state)| (normalize(label), state.clone())),
+        );
+        self
+    }
+
+    pub fn try_classify(&self, text: &str) -> Option<ItemState> {
+        let text = normalize(text);
+        if text.is_empty() {
+            return None;
+        }
+        let padded = format!(" {} ", text);
+        let best_match = |labels: &HashMap<String, ItemState>| {
+            labels
+                .iter()
+                .filter(|(label, _)| padded.contains(&format!(" {} ", label)))
+                // ties between labels of equal length go to the same label on every run
+                .max_by_key(|(label, _)| (label.len(), *label))
+                .map(|(_, state)| state.clone())
+        };
+        best_match(&self.overrides).or_else(|| best_match(&self.labels))
+    }
+
+    pub fn classify(&self, text: &str) -> Option<ItemState> {
+        let state = self.try_classify(text);
+        if state.is_none() {
+            warn!(label = %text, "Unknown availability label, add it to stateLabels.");
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scraper_config::ScraperConfig;
+    use crate::state_classifier::StateClassifier;
+    use item_core::item_state::ItemState::{AVAILABLE, RESERVED, SOLD};
+    use item_core::language::Language::{DE, EN};
+    use std::collections::HashMap;
+
+    #[test]
+    fn should_classify_built_in_labels() {
+        let classifier = StateClassifier::new(Some(&DE));
+        let cases = vec![
+            ("Verkauft", Some(SOLD)),
+            ("  AUSVERKAUFT! ", Some(SOLD)),
+            ("Nicht verfügbar", Some(SOLD)),
+            ("Artikel ist nicht mehr verfügbar.", Some(SOLD)),
+            ("sofort verfügbar", Some(AVAILABLE)),
+            ("Auf Lager", Some(AVAILABLE)),
+            ("Reserviert", Some(RESERVED)),
+            ("on hold", Some(RESERVED)),
+            ("Sold out", Some(SOLD)),
+            ("In stock", Some(AVAILABLE)),
+            ("Neu eingetroffen", None),
+            ("", None),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(
+                classifier.classify(text),
+                expected,
+                "classifying '{}'",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn should_only_know_english_labels_of_other_languages() {
+        let classifier = StateClassifier::new(Some(&EN));
+
+        assert_eq!(classifier.try_classify("verkauft"), None);
+        assert_eq!(classifier.try_classify("sold"), Some(SOLD));
+    }
+
+    #[test]
+    fn should_break_ties_deterministically() {
+        // every classifier iterates its labels in another order
+        for _ in 0..10 {
+            let mut classifier = StateClassifier::new(None);
+            classifier.overrides(&HashMap::from([
+                ("abc".to_string(), SOLD),
+                ("xyz".to_string(), RESERVED),
+            ]));
+
+            assert_eq!(classifier.try_classify("abc xyz"), Some(RESERVED));
+        }
+    }
+
+    #[test]
+    fn should_prefer_shop_overrides() {
+        let scraper_config = ScraperConfig::new("https://foo.bar".to_string())
+            .language(DE)
+            .state_labels(HashMap::from([
+                ("Verkauft".to_string(), RESERVED),
+                ("Preis auf Anfrage".to_string(), AVAILABLE),
+            ]))
+            .clone();
+
+        let classifier = StateClassifier::for_config(&scraper_config);
+
+        assert_eq!(classifier.classify("verkauft"), Some(RESERVED));
+        assert_eq!(classifier.classify("Preis auf Anfrage"), Some(AVAILABLE));
+        assert_eq!(classifier.classify("reserviert"), Some(RESERVED));
+    }
+}
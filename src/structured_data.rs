@@ -0,0 +1,450 @@
+//! Extracts schema.org `Product`s embedded in shop pages: JSON-LD, microdata or, as last resort,
+//! OpenGraph meta tags.
+
+use crate::price_parser::{NumberFormat, parse_amount};
+use ::scraper::{ElementRef, Html, Selector};
+use item_core::item_data::ItemData;
+use item_core::item_state::ItemState;
+use item_core::language::Language;
+use item_core::price::{Currency, Price};
+use serde_json::Value;
+use std::sync::LazyLock;
+
+static JSON_LD_SELECTOR: LazyLock<Selector> =
+    LazyLock::new(|| Selector::parse(r#"script[type="application/ld+json"]"#).unwrap());
+
+static MICRODATA_PRODUCT_SELECTOR: LazyLock<Selector> =
+    LazyLock::new(|| Selector::parse(r#"[itemscope][itemtype*="schema.org/Product"]"#).unwrap());
+
+static ITEMPROP_SELECTOR: LazyLock<Selector> =
+    LazyLock::new(|| Selector::parse("[itemprop]").unwrap());
+
+static META_SELECTOR: LazyLock<Selector> =
+    LazyLock::new(|| Selector::parse("meta[property][content]").unwrap());
+
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct StructuredProduct {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+    pub image_url: Option<String>,
+    pub category: Option<String>,
+    pub price: Option<f32>,
+    pub currency: Option<String>,
+    pub availability: Option<String>,
+}
+
+impl StructuredProduct {
+    /// Identified by `id` or else `url`, nothing if the product has neither.
+    pub fn to_item_data(&self, source_id: &str, language: Option<&Language>) -> Option<ItemData> {
+        let id = self.id.as_ref().or(self.url.as_ref())?;
+        let mut item = ItemData::new(format!("{}#{}", source_id, id))
+            .source_id(source_id.to_string())
+            .to_owned();
+        if let Some(url) = &self.url {
+            item.url(url.clone());
+        }
+        if let Some(state) = self.availability.as_deref().and_then(availability_state) {
+            item.state(state);
+        }
+        let currency = self
+            .currency
+            .as_ref()
+            .and_then(|code| serde_json::from_value::<Currency>(Value::String(code.clone())).ok());
+        if let (Some(currency), Some(amount)) = (currency, self.price) {
+            item.price(Price::new(currency, amount));
+        }
+        if let Some(language) = language {
+            if let Some(name) = &self.name {
+                item.name.insert(language.clone(), name.clone());
+            }
+            if let Some(description) = &self.description {
+                item.description
+                    .insert(language.clone(), description.clone());
+            }
+        }
+        item.category = self.category.clone();
+        item.image_url = self.image_url.clone();
+        Some(item)
+    }
+}
+
+/// Items that can be ordered, including pre-orders, are available.
+pub fn availability_state(availability: &str) -> Option<ItemState> {
+    let name = availability
+        .rsplit('/')
+        .next()
+        .unwrap_or(availability)
+        .trim_start_matches("schema:")
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+    match name.as_str() {
+        "instock"
+        | "onlineonly"
+        | "instoreonly"
+        | "limitedavailability"
+        | "preorder"
+        | "presale"
+        | "backorder" => Some(ItemState::AVAILABLE),
+        "soldout" | "outofstock" | "oos" | "discontinued" => Some(ItemState::SOLD),
+        _ => None,
+    }
+}
+
+/// All products of `html`, taken from the first kind of structured data that has any.
+pub fn extract_products(html: &str) -> Vec<StructuredProduct> {
+    let document = Html::parse_document(html);
+    let json_ld = json_ld_products(&document);
+    if !json_ld.is_empty() {
+        return json_ld;
+    }
+    let microdata = microdata_products(&document);
+    if !microdata.is_empty() {
+        return microdata;
+    }
+    open_graph_product(&document).into_iter().collect()
+}
+
+pub fn extract_item(html: &str, source_id: &str, language: Option<&Language>) -> Option<ItemData> {
+    extract_products(html)
+        .first()
+        .and_then(|product| product.to_item_data(source_id, language))
+}
+
+fn json_ld_products(document: &Html) -> Vec<StructuredProduct> {
+    let mut products = vec![];
+    for script in document.select(&JSON_LD_SELECTOR) {
+        // broken JSON-LD is common enough to just skip it
+        if let Ok(value) = serde_json::from_str::<Value>(&script.text().collect::<String>()) {
+            collect_json_ld_products(&value, &mut products);
+        }
+    }
+    products
+}
+
+fn has_type(value: &Value, wanted: &str) -> bool {
+    let matches = |value: &Value| {
+        value
+            .as_str()
+            .is_some_and(|t| t.rsplit('/').next() == Some(wanted))
+    };
+    match value.get("@type") {
+        Some(Value::Array(types)) => types.iter().any(matches),
+        Some(t) => matches(t),
+        None => false,
+    }
+}
+
+/// Walks the whole document, so products nested in `@graph` or `mainEntity` are found as well.
+fn collect_json_ld_products(value: &Value, products: &mut Vec<StructuredProduct>) {
+    match value {
+        Value::Object(object) if has_type(value, "Product") => {
+            products.push(json_ld_product(value));
+            // variants are products themselves, but no other nested products
+            if let Some(variants) = object.get("hasVariant") {
+                collect_json_ld_products(variants, products);
+            }
+        }
+        Value::Object(object) => object
+            .values()
+            .for_each(|value| collect_json_ld_products(value, products)),
+        Value::Array(values) => values
+            .iter()
+            .for_each(|value| collect_json_ld_products(value, products)),
+        _ => {}
+    }
+}
+
+fn json_text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(string) => Some(string.trim().to_string()).filter(|s| !s.is_empty()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Array(values) => json_text(values.first()),
+        Value::Object(object) => json_text(object.get("url").or(object.get("name"))),
+        _ => None,
+    }
+}
+
+/// schema.org prescribes a decimal point, though shops sometimes add a currency or grouping.
+fn machine_amount(text: &str) -> Option<f32> {
+    parse_amount(text, Some(NumberFormat::DecimalPoint)).ok()
+}
+
+fn json_price(value: Option<&Value>) -> Option<f32> {
+    match value? {
+        Value::Number(number) => number.as_f64().map(|amount| amount as f32),
+        other => json_text(Some(other)).and_then(|text| machine_amount(&text)),
+    }
+}
+
+fn json_ld_product(product: &Value) -> StructuredProduct {
+    let offer = match product.get("offers") {
+        Some(Value::Array(offers)) => offers.first(),
+        offer => offer,
+    };
+    let offer_field = |name: &str| {
+        offer
+            .and_then(|offer| offer.get(name))
+            .or_else(|| offer?.get("priceSpecification")?.get(name))
+    };
+
+    StructuredProduct {
+        id: json_text(product.get("sku").or(product.get("productID"))),
+        name: json_text(product.get("name")),
+        description: json_text(product.get("description")),
+        url: json_text(product.get("url").or(offer_field("url"))),
+        image_url: json_text(product.get("image")),
+        category: json_text(product.get("category")),
+        price: json_price(offer_field("price").or(offer_field("lowPrice"))),
+        currency: json_text(offer_field("priceCurrency")),
+        availability: json_text(offer_field("availability")),
+    }
+}
+
+fn microdata_value(element: ElementRef) -> Option<String> {
+    let value = element.value();
+    let text = value
+        .attr("content")
+        .or_else(|| match value.name() {
+            "a" | "link" => value.attr("href"),
+            "img" => value.attr("src"),
+            _ => None,
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| element.text().collect::<Vec<_>>().join(" "));
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(text).filter(|text| !text.is_empty())
+}
+
+fn microdata_products(document: &Html) -> Vec<StructuredProduct> {
+    document
+        .select(&MICRODATA_PRODUCT_SELECTOR)
+        .map(|element| {
+            let mut product = StructuredProduct::default();
+            // offers are nested item scopes, but their properties are just as well the product's
+            for property in element
+                .select(&ITEMPROP_SELECTOR)
+                .filter(|property| !in_nested_product(*property, element))
+            {
+                let value = microdata_value(property);
+                let target = match property.value().attr("itemprop").unwrap_or_default() {
+                    "sku" | "productID" => &mut product.id,
+                    "name" => &mut product.name,
+                    "description" => &mut product.description,
+                    "url" => &mut product.url,
+                    "image" => &mut product.image_url,
+                    "category" => &mut product.category,
+                    "priceCurrency" => &mut product.currency,
+                    "availability" => &mut product.availability,
+                    "price" | "lowPrice" => {
+                        if product.price.is_none() {
+                            product.price = value.and_then(|text| machine_amount(&text));
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if target.is_none() {
+                    *target = value;
+                }
+            }
+            product
+        })
+        .collect()
+}
+
+/// Related products (`isRelatedTo`, `isSimilarTo`, ...) are item scopes of their own.
+fn in_nested_product(property: ElementRef, product: ElementRef) -> bool {
+    property
+        .ancestors()
+        .take_while(|ancestor| ancestor.id() != product.id())
+        .filter_map(ElementRef::wrap)
+        .any(|ancestor| MICRODATA_PRODUCT_SELECTOR.matches(&ancestor))
+}
+
+fn open_graph_product(document: &Html) -> Option<StructuredProduct> {
+    let mut product = StructuredProduct::default();
+    for meta in document.select(&META_SELECTOR) {
+        let value = meta.value();
+        let content = value
+            .attr("content")
+            .map(|content| content.trim().to_string());
+        let target = match value.attr("property").unwrap_or_default() {
+            "product:retailer_item_id" => &mut product.id,
+            "og:title" => &mut product.name,
+            "og:description" => &mut product.description,
+            "og:url" => &mut product.url,
+            "og:image" => &mut product.image_url,
+            "product:category" => &mut product.category,
+            "product:price:currency" | "og:price:currency" => &mut product.currency,
+            "product:availability" | "og:availability" => &mut product.availability,
+            "product:price:amount" | "og:price:amount" => {
+                product.price = product
+                    .price
+                    .or_else(|| content.and_then(|text| machine_amount(&text)));
+                continue;
+            }
+            _ => continue,
+        };
+        if target.is_none() {
+            *target = content.filter(|content| !content.is_empty());
+        }
+    }
+    // any page has a title, only a price or availability makes it a product
+    (product.price.is_some() || product.availability.is_some()).then_some(product)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structured_data::{
+        StructuredProduct, availability_state, extract_item, extract_products,
+    };
+    use item_core::item_state::ItemState::{AVAILABLE, SOLD};
+    use item_core::language::Language::EN;
+    use item_core::price::Currency::EUR;
+    use item_core::price::Price;
+
+    #[test]
+    fn should_extract_json_ld_products_from_graph() {
+        let html = r#"
+            <html><head>
+                <script type="application/ld+json">{ not json }</script>
+                <script type="application/ld+json">
+                {
+                    "@context": "https://schema.org",
+                    "@graph": [
+                        { "@type": "BreadcrumbList", "name": "Shop" },
+                        {
+                            "@type": "Product",
+                            "sku": "M1895",
+                            "name": "Pickelhaube",
+                            "image": ["https://foo.bar/1.jpg", "https://foo.bar/2.jpg"],
+                            "url": "https://foo.bar/p/m1895",
+                            "offers": [{
+                                "@type": "Offer",
+                                "price": "1299.00",
+                                "priceCurrency": "EUR",
+                                "availability": "https://schema.org/SoldOut"
+                            }]
+                        }
+                    ]
+                }
+                </script>
+            </head></html>
+        "#;
+
+        let products = extract_products(html);
+
+        assert_eq!(
+            products,
+            vec![StructuredProduct {
+                id: Some("M1895".to_string()),
+                name: Some("Pickelhaube".to_string()),
+                description: None,
+                url: Some("https://foo.bar/p/m1895".to_string()),
+                image_url: Some("https://foo.bar/1.jpg".to_string()),
+                category: None,
+                price: Some(1299.0),
+                currency: Some("EUR".to_string()),
+                availability: Some("https://schema.org/SoldOut".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_fall_back_to_microdata() {
+        let html = r#"
+            <div itemscope itemtype="https://schema.org/Product">
+                <h1 itemprop="name">Koppelschloss</h1>
+                <meta itemprop="sku" content="KS-1">
+                <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                    <span itemprop="price" content="85.50">85,50 €</span>
+                    <meta itemprop="priceCurrency" content="EUR">
+                    <link itemprop="availability" href="https://schema.org/InStock">
+                </div>
+            </div>
+        "#;
+
+        let item = extract_item(html, "https://foo.bar", Some(&EN)).unwrap();
+
+        assert_eq!(item.item_id, "https://foo.bar#KS-1");
+        assert_eq!(item.name.get(&EN), Some(&"Koppelschloss".to_string()));
+        assert_eq!(item.price, Some(Price::new(EUR, 85.5)));
+        assert_eq!(item.state, Some(AVAILABLE));
+    }
+
+    #[test]
+    fn should_ignore_properties_of_related_microdata_products() {
+        let html = r#"
+            <div itemscope itemtype="https://schema.org/Product">
+                <h1 itemprop="name">Koppelschloss</h1>
+                <div itemprop="isRelatedTo" itemscope itemtype="https://schema.org/Product">
+                    <span itemprop="name">Koppel</span>
+                    <meta itemprop="sku" content="K-1">
+                    <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                        <meta itemprop="price" content="20.00">
+                    </div>
+                </div>
+                <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                    <meta itemprop="priceCurrency" content="EUR">
+                </div>
+            </div>
+        "#;
+
+        let products = extract_products(html);
+
+        assert_eq!(products.len(), 2);
+        assert_eq!(products[0].name, Some("Koppelschloss".to_string()));
+        assert_eq!(products[0].id, None);
+        assert_eq!(products[0].price, None);
+        assert_eq!(products[0].currency, Some("EUR".to_string()));
+        assert_eq!(products[1].name, Some("Koppel".to_string()));
+        assert_eq!(products[1].price, Some(20.0));
+    }
+
+    #[test]
+    fn should_fall_back_to_open_graph() {
+        let html = r#"
+            <head>
+                <meta property="og:title" content="Feldflasche">
+                <meta property="og:url" content="https://foo.bar/p/ff">
+                <meta property="product:price:amount" content="42.00">
+                <meta property="product:price:currency" content="EUR">
+                <meta property="product:availability" content="out of stock">
+            </head>
+        "#;
+
+        let item = extract_item(html, "https://foo.bar", None).unwrap();
+
+        assert_eq!(item.item_id, "https://foo.bar#https://foo.bar/p/ff");
+        assert_eq!(item.price, Some(Price::new(EUR, 42.0)));
+        assert_eq!(item.state, Some(SOLD));
+        assert!(item.name.is_empty());
+    }
+
+    #[test]
+    fn should_not_treat_plain_pages_as_products() {
+        let html = r#"<head><meta property="og:title" content="About us"></head>"#;
+
+        assert!(extract_products(html).is_empty());
+    }
+
+    #[test]
+    fn should_map_availabilities() {
+        assert_eq!(
+            availability_state("https://schema.org/InStock"),
+            Some(AVAILABLE)
+        );
+        assert_eq!(
+            availability_state("http://schema.org/PreOrder"),
+            Some(AVAILABLE)
+        );
+        assert_eq!(availability_state("schema:OutOfStock"), Some(SOLD));
+        assert_eq!(availability_state("SoldOut"), Some(SOLD));
+        assert_eq!(availability_state("oos"), Some(SOLD));
+        assert_eq!(availability_state("Unknown"), None);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::scraper::ScrapeError;
+use reqwest::StatusCode;
+use reqwest::header::RETRY_AFTER;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+/// The delay the pacing falls back to when a shop throttles us without saying for how long.
+pub const MIN_RATE_LIMIT_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(default)]
+pub struct ThrottlePolicy {
+    /// Upper bound for both the wait before re-requesting a throttled page
+    /// and the slowed down delay between pages.
+    #[serde(rename = "maxDelayMillis")]
+    pub max_delay_millis: u64,
+
+    #[serde(rename = "maxRetriesPerPage")]
+    pub max_retries_per_page: u32,
+}
+
+impl Default for ThrottlePolicy {
+    fn default() -> Self {
+        ThrottlePolicy {
+            max_delay_millis: 60_000,
+            max_retries_per_page: 5,
+        }
+    }
+}
+
+impl ThrottlePolicy {
+    // region fluent_setter
+
+    pub fn max_delay_millis(&mut self, max_delay_millis: u64) -> &mut Self {
+        self.max_delay_millis = max_delay_millis;
+        self
+    }
+
+    pub fn max_retries_per_page(&mut self, max_retries_per_page: u32) -> &mut Self {
+        self.max_retries_per_page = max_retries_per_page;
+        self
+    }
+
+    // endregion
+}
+
+/// The delay between pages of a single run. It starts at `sleepBetweenPagesMillis`
+/// and only ever grows once the shop starts throttling us.
+#[derive(Debug, Clone)]
+pub struct AdaptivePacing {
+    delay: Duration,
+    max_delay: Duration,
+}
+
+impl AdaptivePacing {
+    pub fn new(initial_delay: Duration, throttle_policy: &ThrottlePolicy) -> Self {
+        AdaptivePacing {
+            delay: initial_delay,
+            max_delay: Duration::from_millis(throttle_policy.max_delay_millis),
+        }
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Never goes below `min_delay`, e.g. a robots.txt `Crawl-delay`.
+    pub fn at_least(&mut self, min_delay: Duration) {
+        self.delay = self.delay.max(min_delay);
+    }
+
+    pub fn slow_down(&mut self, retry_after: Option<Duration>) -> Duration {
+        let doubled = self.delay.saturating_mul(2).max(MIN_RATE_LIMIT_DELAY);
+        self.delay = doubled
+            .max(retry_after.unwrap_or_default())
+            .min(self.max_delay);
+
+        retry_after.unwrap_or(self.delay).min(self.max_delay)
+    }
+}
+
+/// Applied to every response of [`crate::scrape_client::ScrapeClient`], scrapers using another
+/// client should pass their responses through this before reading the body.
+pub fn check_rate_limit(response: reqwest::Response) -> Result<reqwest::Response, ScrapeError> {
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, SystemTime::now()));
+
+        Err(ScrapeError::RateLimited {
+            status: status.as_u16(),
+            url: response.url().to_string(),
+            retry_after,
+        })
+    } else {
+        Ok(response)
+    }
+}
+
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()
+            .map(|date| date.duration_since(now).unwrap_or_default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::throttle::{
+        AdaptivePacing, MIN_RATE_LIMIT_DELAY, ThrottlePolicy, parse_retry_after,
+    };
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn should_parse_retry_after_seconds() {
+        let actual = parse_retry_after("120", SystemTime::now());
+
+        assert_eq!(actual, Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn should_parse_retry_after_http_date() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+
+        let actual = parse_retry_after("Wed, 21 Oct 2015 07:29:30 GMT", now);
+
+        assert_eq!(actual, Some(Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn should_not_parse_invalid_retry_after() {
+        assert_eq!(parse_retry_after("soon", SystemTime::now()), None);
+    }
+
+    #[test]
+    fn should_slow_down_at_least_to_min_delay() {
+        let mut pacing = AdaptivePacing::new(Duration::ZERO, &ThrottlePolicy::default());
+
+        let wait = pacing.slow_down(None);
+
+        assert_eq!(wait, MIN_RATE_LIMIT_DELAY);
+        assert_eq!(pacing.delay(), MIN_RATE_LIMIT_DELAY);
+    }
+
+    #[test]
+    fn should_slow_down_to_retry_after_and_stay_capped() {
+        let throttle_policy = ThrottlePolicy::default().max_delay_millis(10_000).clone();
+        let mut pacing = AdaptivePacing::new(Duration::from_millis(500), &throttle_policy);
+
+        let wait = pacing.slow_down(Some(Duration::from_secs(4)));
+        assert_eq!(wait, Duration::from_secs(4));
+        assert_eq!(pacing.delay(), Duration::from_secs(4));
+
+        pacing.slow_down(None);
+        let wait = pacing.slow_down(Some(Duration::from_secs(3600)));
+        assert_eq!(wait, Duration::from_secs(10));
+        assert_eq!(pacing.delay(), Duration::from_secs(10));
+    }
+}
@@ -5,9 +5,12 @@ use item_core::language::Language::{DE, EN};
 use item_core::price::Currency::EUR;
 use item_core::price::Price;
 use item_read::item_hash::get_item_event_hashes_by_source_id;
+use scrape::hash_store::DynamoDbHashStore;
 use scrape::scrape_and_push;
+use scrape::scrape_client::ScrapeClient;
 use scrape::scraper::{ScrapeError, Scraper};
 use scrape::scraper_config::ScraperConfig;
+use scrape::sqs_sink::SqsSink;
 use std::collections::HashMap;
 use std::time::Duration;
 use test_api::generator::Generator;
@@ -24,7 +27,7 @@ async fn should_scrape_and_push() {
         async fn scrape_page(
             &self,
             page_num: i16,
-            _: &reqwest::Client,
+            _: &ScrapeClient,
         ) -> Result<Vec<ItemData>, ScrapeError> {
             match page_num {
                 1 => Ok(vec![
@@ -45,14 +48,20 @@ async fn should_scrape_and_push() {
         &scraper,
         &scraper_config,
         &reqwest_client,
-        get_sqs_client().await,
-        get_dynamodb_client().await,
-        "http://sqs.eu-central-1.localhost.localstack.cloud:4566/000000000000/write_lambda_queue",
+        &SqsSink::new(
+            get_sqs_client().await.clone(),
+            "http://sqs.eu-central-1.localhost.localstack.cloud:4566/000000000000/write_lambda_queue"
+                .to_string(),
+        ),
+        &DynamoDbHashStore::new(get_dynamodb_client().await.clone()),
     )
     .await;
     assert!(scrape_and_push_res.is_ok());
-    let pushed_count = scrape_and_push_res.unwrap();
-    assert_eq!(pushed_count, 1);
+    let report = scrape_and_push_res.unwrap();
+    assert_eq!(report.items_pushed, 1);
+    assert_eq!(report.items_scraped, 1);
+    assert_eq!(report.pages_scraped, 2);
+    assert!(report.push_failures.is_empty());
 
     // Wait for SQS and Lambda to work...
     sleep(Duration::from_secs(15)).await;
@@ -74,7 +83,7 @@ async fn should_scrape_and_push_only_diffs() {
         async fn scrape_page(
             &self,
             page_num: i16,
-            _: &reqwest::Client,
+            _: &ScrapeClient,
         ) -> Result<Vec<ItemData>, ScrapeError> {
             match page_num {
                 1 => Ok(vec![ItemData {
@@ -103,7 +112,7 @@ async fn should_scrape_and_push_only_diffs() {
         async fn scrape_page(
             &self,
             page_num: i16,
-            _: &reqwest::Client,
+            _: &ScrapeClient,
         ) -> Result<Vec<ItemData>, ScrapeError> {
             match page_num {
                 1 => Ok(vec![
@@ -151,13 +160,16 @@ async fn should_scrape_and_push_only_diffs() {
         &scraper1,
         &scraper_config,
         &reqwest_client,
-        get_sqs_client().await,
-        get_dynamodb_client().await,
-        "http://sqs.eu-central-1.localhost.localstack.cloud:4566/000000000000/write_lambda_queue",
+        &SqsSink::new(
+            get_sqs_client().await.clone(),
+            "http://sqs.eu-central-1.localhost.localstack.cloud:4566/000000000000/write_lambda_queue"
+                .to_string(),
+        ),
+        &DynamoDbHashStore::new(get_dynamodb_client().await.clone()),
     )
     .await;
     assert!(scrape_and_push_res.is_ok());
-    let pushed_count = scrape_and_push_res.unwrap();
+    let pushed_count = scrape_and_push_res.unwrap().items_pushed;
     assert_eq!(pushed_count, 1);
 
     // Wait for SQS and Lambda to work...
@@ -178,13 +190,16 @@ async fn should_scrape_and_push_only_diffs() {
         &scraper2,
         &scraper_config,
         &reqwest_client,
-        get_sqs_client().await,
-        get_dynamodb_client().await,
-        "http://sqs.eu-central-1.localhost.localstack.cloud:4566/000000000000/write_lambda_queue",
+        &SqsSink::new(
+            get_sqs_client().await.clone(),
+            "http://sqs.eu-central-1.localhost.localstack.cloud:4566/000000000000/write_lambda_queue"
+                .to_string(),
+        ),
+        &DynamoDbHashStore::new(get_dynamodb_client().await.clone()),
     )
     .await;
     assert!(scrape_and_push_res.is_ok());
-    let pushed_count = scrape_and_push_res.unwrap();
+    let pushed_count = scrape_and_push_res.unwrap().items_pushed;
     assert_eq!(pushed_count, 1);
 
     // Wait for SQS and Lambda to work...
@@ -207,7 +222,7 @@ async fn should_scrape_and_push_nothing_when_no_diffs() {
         async fn scrape_page(
             &self,
             page_num: i16,
-            _: &reqwest::Client,
+            _: &ScrapeClient,
         ) -> Result<Vec<ItemData>, ScrapeError> {
             match page_num {
                 1 => Ok(vec![ItemData {
@@ -238,13 +253,16 @@ async fn should_scrape_and_push_nothing_when_no_diffs() {
         &scraper,
         &scraper_config,
         &reqwest_client,
-        get_sqs_client().await,
-        get_dynamodb_client().await,
-        "http://sqs.eu-central-1.localhost.localstack.cloud:4566/000000000000/write_lambda_queue",
+        &SqsSink::new(
+            get_sqs_client().await.clone(),
+            "http://sqs.eu-central-1.localhost.localstack.cloud:4566/000000000000/write_lambda_queue"
+                .to_string(),
+        ),
+        &DynamoDbHashStore::new(get_dynamodb_client().await.clone()),
     )
         .await;
     assert!(scrape_and_push_res.is_ok());
-    let pushed_count = scrape_and_push_res.unwrap();
+    let pushed_count = scrape_and_push_res.unwrap().items_pushed;
     assert_eq!(pushed_count, 1);
 
     // Wait for SQS and Lambda to work...
@@ -263,13 +281,16 @@ async fn should_scrape_and_push_nothing_when_no_diffs() {
         &scraper,
         &scraper_config,
         &reqwest_client,
-        get_sqs_client().await,
-        get_dynamodb_client().await,
-        "http://sqs.eu-central-1.localhost.localstack.cloud:4566/000000000000/write_lambda_queue",
+        &SqsSink::new(
+            get_sqs_client().await.clone(),
+            "http://sqs.eu-central-1.localhost.localstack.cloud:4566/000000000000/write_lambda_queue"
+                .to_string(),
+        ),
+        &DynamoDbHashStore::new(get_dynamodb_client().await.clone()),
     )
         .await;
     assert!(scrape_and_push_res.is_ok());
-    let pushed_count = scrape_and_push_res.unwrap();
+    let pushed_count = scrape_and_push_res.unwrap().items_pushed;
     assert_eq!(pushed_count, 0);
 
     // Wait for SQS and Lambda to work...